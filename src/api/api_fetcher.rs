@@ -1,7 +1,22 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, TryStreamExt};
+use rand::Rng;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    StatusCode,
+};
 use serde::de::DeserializeOwned;
 use thiserror::Error;
 
-use crate::models::{DepthInterval, EarningInterval, RunePoolInterval, SwapsInterval};
+use crate::api::{response_archive::ResponseArchive, schema_drift::{DriftChecked, SchemaDrift}};
+use crate::models::{Action, Asset, PoolScope, Interval, TimeWindow, WindowError, MAX_INTERVAL_COUNT, DeadLetter, DepthInterval, DepthMeta, EarningInterval, EarningsMeta, HistoryInterval, HistoryResponse, MemberPool, PoolDetail, RunePoolInterval, RunePoolMeta, RunePoolProvider, SaverPosition, SwapsInterval, SwapsMeta, TvlInterval, TvlMeta};
 
 // Error handling
 #[derive(Error, Debug)]
@@ -14,84 +29,988 @@ pub(crate) enum ApiError {
 
     #[error("Invalid response structure: {0}")]
     InvalidResponse(String),
+
+    #[error("Invalid API configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Rate limited by Midgard (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("Midgard server error: {status}")]
+    ServerError { status: StatusCode, retry_after: Option<Duration> },
+
+    #[error("Midgard rejected the request with {status}: {body}")]
+    ClientError { status: StatusCode, body: String },
+
+    #[error("Response body is not valid JSON: {0}")]
+    MalformedBody(serde_json::Error),
+
+    #[error("Invalid time window: {0}")]
+    InvalidWindow(#[from] WindowError),
+
+    #[error("Giving up after {attempts} attempts: {last}")]
+    RetriesExhausted { attempts: u32, last: Box<ApiError> },
+}
+
+impl ApiError {
+    // Transient failures worth another attempt; everything else aborts the fetch
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApiError::RequestError(e) => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+            ApiError::RateLimited { .. } | ApiError::ServerError { .. } | ApiError::MalformedBody(_) => true,
+            _ => false,
+        }
+    }
+
+    // Failures that point at the mirror itself rather than the request
+    fn is_mirror_failure(&self) -> bool {
+        match self {
+            ApiError::RequestError(e) => e.is_timeout() || e.is_connect(),
+            ApiError::ServerError { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ApiError::RateLimited { retry_after } | ApiError::ServerError { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+// Retry policy for transient Midgard failures
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    // Exponential delay before retrying after the given (1-based) attempt, optionally
+    // jittered into [delay / 2, delay] so concurrent callers don't retry in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let delay = exp.min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+        } else {
+            delay
+        }
+    }
+
+    // Delay before the next attempt: the server's Retry-After when it sent one, capped at
+    // `max_delay` so a far-off date cannot stall the run, otherwise the backoff
+    fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.backoff(attempt),
+        }
+    }
+}
+
+// Token bucket settings: sustained request rate and how many requests may be sent back to back
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 5.0,
+            burst: 10,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    // Available tokens (negative when callers are queued) and when they were last refilled
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(config: &RateLimitConfig) -> Self {
+        let burst = f64::from(config.burst.max(1));
+        Self {
+            rate: config.requests_per_second,
+            burst,
+            state: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    // Takes a token, sleeping until it is available. Tokens are reserved up front
+    // so concurrent callers queue behind each other instead of racing.
+    async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let (tokens, last_refill) = &mut *state;
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*last_refill).as_secs_f64() * self.rate).min(self.burst);
+            *last_refill = now;
+            *tokens -= 1.0;
+            if *tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-*tokens / self.rate)
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
 }
 
 // API client configuration
 #[derive(Debug, Clone)]
 pub struct ApiConfig {
-    pub base_url: String,
+    // Midgard mirrors, tried in order
+    pub base_urls: Vec<String>,
+    pub mirror_cooldown_secs: u64,
     pub timeout_secs: u64,
+    pub user_agent: String,
+    pub headers: Vec<(String, String)>,
+    pub proxy: Option<String>,
+    pub retry: RetryConfig,
+    // `None` disables client-side throttling
+    pub rate_limit: Option<RateLimitConfig>,
+    // Directory raw responses are archived to; `None` disables the archive
+    pub archive_dir: Option<String>,
+    // Keep fields the models do not know in their `extra` map
+    pub capture_unknown_fields: bool,
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
-            base_url: "https://midgard.ninerealms.com/v2".to_string(),
+            base_urls: vec!["https://midgard.ninerealms.com/v2".to_string()],
+            mirror_cooldown_secs: 60,
             timeout_secs: 30,
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string(),
+            headers: Vec::new(),
+            proxy: None,
+            retry: RetryConfig::default(),
+            rate_limit: Some(RateLimitConfig::default()),
+            archive_dir: None,
+            capture_unknown_fields: false,
+        }
+    }
+}
+
+fn env_parse<T: FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+impl ApiConfig {
+    // Defaults overridden by the MIDGARD_* environment variables.
+    // MIDGARD_BASE_URLS is a comma separated list of mirrors and
+    // MIDGARD_HEADERS a comma separated list of `Name: value` pairs.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let headers = env::var("MIDGARD_HEADERS")
+            .map(|raw| {
+                raw.split(',')
+                    .filter_map(|pair| pair.split_once(':'))
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or(default.headers);
+
+        Self {
+            base_urls: env::var("MIDGARD_BASE_URLS")
+                .map(|raw| raw.split(',').map(|url| url.trim().to_string()).filter(|url| !url.is_empty()).collect())
+                .unwrap_or(default.base_urls),
+            mirror_cooldown_secs: env_parse("MIDGARD_MIRROR_COOLDOWN_SECS").unwrap_or(default.mirror_cooldown_secs),
+            timeout_secs: env_parse("MIDGARD_TIMEOUT_SECS").unwrap_or(default.timeout_secs),
+            user_agent: env::var("MIDGARD_USER_AGENT").unwrap_or(default.user_agent),
+            headers,
+            proxy: env::var("MIDGARD_PROXY").ok().or(default.proxy),
+            retry: RetryConfig {
+                max_attempts: env_parse("MIDGARD_MAX_ATTEMPTS").unwrap_or(default.retry.max_attempts),
+                base_delay: env_parse("MIDGARD_RETRY_BASE_MS").map(Duration::from_millis).unwrap_or(default.retry.base_delay),
+                max_delay: env_parse("MIDGARD_RETRY_MAX_MS").map(Duration::from_millis).unwrap_or(default.retry.max_delay),
+                jitter: default.retry.jitter,
+            },
+            rate_limit: default.rate_limit.map(|limit| RateLimitConfig {
+                requests_per_second: env_parse("MIDGARD_RATE_LIMIT_RPS").unwrap_or(limit.requests_per_second),
+                burst: env_parse("MIDGARD_RATE_LIMIT_BURST").unwrap_or(limit.burst),
+            }),
+            archive_dir: env::var("MIDGARD_ARCHIVE_DIR").ok().or(default.archive_dir),
+            capture_unknown_fields: env_parse("MIDGARD_CAPTURE_UNKNOWN_FIELDS").unwrap_or(default.capture_unknown_fields),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct IntervalParams {
-    pub from: i64,
+    pub interval: Interval,
+    pub from: DateTime<Utc>,
     pub count: i64,
-    pub interval: String,
+    // Restricts endpoints that accept `?pool=ASSET` to a single pool
+    pub pool: Option<Asset>,
 }
 
+impl IntervalParams {
+    // `count` intervals of `interval` starting with the one containing `from`, within Midgard's limits
+    pub fn new(interval: Interval, from: DateTime<Utc>, count: i64, pool: Option<Asset>) -> Result<Self, WindowError> {
+        if from.timestamp() < 0 {
+            return Err(WindowError::BeforeEpoch(from.timestamp()));
+        }
+        if !(1..=MAX_INTERVAL_COUNT).contains(&count) {
+            return Err(WindowError::Count(count));
+        }
+        Ok(Self { interval, from, count, pool })
+    }
+}
 
+// Filters for `/actions`; every action since `from_timestamp` is fetched
+#[derive(Debug, Clone, Default)]
+pub struct ActionsParams {
+    pub from_timestamp: Option<i64>,
+    // Comma separated action types, e.g. "swap,addLiquidity"
+    pub action_type: Option<String>,
+    // Comma separated assets, or Midgard's `nosynth` / `norune` filters
+    pub asset: Option<String>,
+    pub address: Option<String>,
+}
 
-pub async fn fetch_interval_data<T: DeserializeOwned>(
-    endpoint: &str,
-    params: &IntervalParams,
-) -> Result<Vec<T>, ApiError> {
-    let url = format!(
-        "{}/history/{}?interval={}&count={}&from={}",
-        ApiConfig::default().base_url, endpoint, params.interval, params.count, params.from
-    );
+// Midgard's page size limit for `/actions`
+pub const MAX_ACTIONS_LIMIT: u32 = 50;
 
-    println!("Fetching data from URL: {}", url);
+// Time window walked page by page by the backfill helpers
+#[derive(Debug, Clone)]
+pub struct BackfillRange {
+    pub window: TimeWindow,
+    pub interval: Interval,
+}
 
-    let response = reqwest::get(&url)
-        .await
-        .map_err(ApiError::RequestError)?;
+// Accepts either delta-seconds or an HTTP date
+fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
 
-    let json_resp: serde_json::Value = response
-        .json()
+async fn get_json_once(client: &reqwest::Client, url: &str) -> Result<serde_json::Value, ApiError> {
+    let response = client
+        .get(url)
+        .send()
         .await
         .map_err(ApiError::RequestError)?;
 
-    let intervals = json_resp["intervals"]
-        .as_array()
-        .ok_or_else(|| ApiError::InvalidResponse("No intervals found in response".to_string()))?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(ApiError::RateLimited { retry_after: parse_retry_after(&response) });
+    }
+    if status.is_server_error() {
+        return Err(ApiError::ServerError { status, retry_after: parse_retry_after(&response) });
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ApiError::ClientError { status, body });
+    }
+
+    let body = response.text().await.map_err(ApiError::RequestError)?;
+    serde_json::from_str(&body).map_err(ApiError::MalformedBody)
+}
+
+// Midgard client owning a pooled HTTP client; cheap to clone
+#[derive(Debug, Clone)]
+pub struct MidgardClient {
+    http: reqwest::Client,
+    config: ApiConfig,
+    // Per mirror: when it becomes eligible again after a failure
+    unhealthy_until: Arc<Mutex<Vec<Option<Instant>>>>,
+    // Shared by every clone so the limit applies to the whole process
+    rate_limiter: Option<Arc<RateLimiter>>,
+    archive: Option<ResponseArchive>,
+    drift: Arc<SchemaDrift>,
+}
+
+impl MidgardClient {
+    pub fn new(mut config: ApiConfig) -> Result<Self, ApiError> {
+        if config.base_urls.is_empty() {
+            return Err(ApiError::InvalidConfig("no Midgard base URLs configured".to_string()));
+        }
+        if let Some(limit) = &config.rate_limit {
+            if !limit.requests_per_second.is_finite() || limit.requests_per_second <= 0.0 {
+                return Err(ApiError::InvalidConfig("rate limit must be a positive number of requests per second".to_string()));
+            }
+        }
+        for url in config.base_urls.iter_mut() {
+            *url = url.trim_end_matches('/').to_string();
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ApiError::InvalidConfig(format!("header name {:?}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| ApiError::InvalidConfig(format!("header value for {}: {}", name, e)))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .user_agent(config.user_agent.as_str())
+            .default_headers(headers);
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+        }
+
+        let unhealthy_until = Arc::new(Mutex::new(vec![None; config.base_urls.len()]));
+        let rate_limiter = config.rate_limit.as_ref().map(|limit| Arc::new(RateLimiter::new(limit)));
+        let archive = config.archive_dir.as_ref().map(ResponseArchive::new);
+        let drift = Arc::new(SchemaDrift::new(config.capture_unknown_fields));
+        Ok(Self { http: builder.build()?, config, unhealthy_until, rate_limiter, archive, drift })
+    }
+
+    // Fields seen so far that the models and Midgard disagree on
+    pub fn schema_drift(&self) -> &SchemaDrift {
+        &self.drift
+    }
+
+    // Healthy mirrors in configured order; when every mirror is cooling down,
+    // all of them ordered by whichever recovers first
+    fn mirror_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let unhealthy_until = self.unhealthy_until.lock().unwrap();
+        let healthy: Vec<usize> = (0..unhealthy_until.len())
+            .filter(|&i| unhealthy_until[i].is_none_or(|until| until <= now))
+            .collect();
+        if !healthy.is_empty() {
+            return healthy;
+        }
+        let mut all: Vec<usize> = (0..unhealthy_until.len()).collect();
+        all.sort_by_key(|&i| unhealthy_until[i]);
+        all
+    }
+
+    fn mark_mirror(&self, index: usize, healthy: bool) {
+        let cooldown = Duration::from_secs(self.config.mirror_cooldown_secs);
+        self.unhealthy_until.lock().unwrap()[index] = if healthy { None } else { Some(Instant::now() + cooldown) };
+    }
+
+    // GETs `path` from the first mirror that answers, failing over on connection
+    // errors, timeouts and 5xx responses
+    async fn get_json_from_mirrors(&self, path: &str) -> Result<serde_json::Value, ApiError> {
+        let mut last_error = None;
+        for index in self.mirror_order() {
+            let url = format!("{}{}", self.config.base_urls[index], path);
+            println!("Fetching data from URL: {}", url);
+
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+
+            match get_json_once(&self.http, &url).await {
+                Ok(json) => {
+                    self.mark_mirror(index, true);
+                    return Ok(json);
+                }
+                Err(e) if e.is_mirror_failure() => {
+                    eprintln!(
+                        "Mirror {} failed: {}. Skipping it for {}s",
+                        self.config.base_urls[index], e, self.config.mirror_cooldown_secs
+                    );
+                    self.mark_mirror(index, false);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| ApiError::InvalidConfig("no Midgard base URLs configured".to_string())))
+    }
+
+    // GETs `path`, retrying transient failures with exponential backoff and honoring `Retry-After`
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value, ApiError> {
+        let retry = &self.config.retry;
+        let mut attempt = 1;
+        loop {
+            match self.get_json_from_mirrors(path).await {
+                Ok(json) => {
+                    self.archive_response(path, &json).await;
+                    return Ok(json);
+                }
+                Err(e) if e.is_retryable() && attempt < retry.max_attempts => {
+                    let delay = retry.retry_delay(attempt, e.retry_after());
+                    eprintln!(
+                        "Attempt {}/{} for {} failed: {}. Retrying in {:?}",
+                        attempt, retry.max_attempts, path, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) if e.is_retryable() => {
+                    return Err(ApiError::RetriesExhausted { attempts: attempt, last: Box::new(e) })
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // A failing archive never fails the fetch itself
+    async fn archive_response(&self, path: &str, json: &serde_json::Value) {
+        if let Some(archive) = &self.archive {
+            if let Err(e) = archive.write(path, json).await {
+                eprintln!("Failed to archive response of {}: {}", path, e);
+            }
+        }
+    }
+
+    // Like `get_json`, but a 404 (e.g. an address that is not a member) yields `None`
+    async fn get_json_opt(&self, path: &str) -> Result<Option<serde_json::Value>, ApiError> {
+        match self.get_json(path).await {
+            Ok(json) => Ok(Some(json)),
+            Err(ApiError::ClientError { status: StatusCode::NOT_FOUND, .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Fetches a single page: its intervals together with the response's `meta` block
+    pub async fn fetch_interval_data<T: DriftChecked, M: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &IntervalParams,
+    ) -> Result<HistoryResponse<T, M>, ApiError> {
+        let mut path = format!(
+            "/history/{}?interval={}&count={}&from={}",
+            endpoint, params.interval, params.count, params.from.timestamp()
+        );
+        if let Some(pool) = &params.pool {
+            path.push_str(&format!("&pool={}", pool));
+        }
+
+        parse_history_page(self.get_json(&path).await?, &path, &self.drift)
+    }
+
+    // Walks `range` forward one page at a time, continuing from the `meta.endTime`
+    // of the previous response, and keeps every interval that closes by the end of the window.
+    // Pages are yielded in order, each with the `meta` block of its window; the next page
+    // is only requested once the stream is polled again, and the stream ends at the first error.
+    pub fn backfill_interval_data<T, M>(
+        &self,
+        endpoint: String,
+        range: &BackfillRange,
+        pool: Option<&Asset>,
+    ) -> impl Stream<Item = Result<HistoryResponse<T, M>, ApiError>> + '_
+    where
+        T: DriftChecked + HistoryInterval,
+        M: DeserializeOwned + HistoryInterval,
+    {
+        let range = range.clone();
+        let pool = pool.cloned();
+        futures::stream::try_unfold(Some(range.window.start()), move |cursor| {
+            let (endpoint, range, pool) = (endpoint.clone(), range.clone(), pool.clone());
+            async move {
+                let Some(cursor) = cursor.filter(|cursor| *cursor < range.window.end()) else { return Ok(None) };
+                let params = IntervalParams::new(range.interval, cursor, MAX_INTERVAL_COUNT, pool)?;
+                let mut page: HistoryResponse<T, M> = self.fetch_interval_data(&endpoint, &params).await?;
+                page.intervals.retain(|interval| interval.end_time() <= range.window.end_time());
+                for interval in page.intervals.iter_mut() {
+                    interval.set_granularity(range.interval);
+                }
+                page.meta.set_granularity(range.interval);
+
+                // Midgard stops at the current (still open) interval, so no progress means we are done
+                let meta_end_time = page.meta.end_time();
+                let next = if meta_end_time <= cursor.timestamp() {
+                    None
+                } else {
+                    Some(DateTime::from_timestamp(meta_end_time, 0).ok_or(WindowError::OutOfRange(meta_end_time))?)
+                };
+                Ok::<_, ApiError>(Some((page, next)))
+            }
+        })
+    }
+
+    // Every pool listed by `/pools`
+    pub async fn fetch_pools(&self) -> Result<Vec<PoolDetail>, ApiError> {
+        let json_resp = self.get_json("/pools").await?;
+        serde_json::from_value(json_resp).map_err(ApiError::ParseError)
+    }
+
+    pub async fn fetch_pool(&self, asset: &Asset) -> Result<PoolDetail, ApiError> {
+        let json_resp = self.get_json(&format!("/pool/{}", asset)).await?;
+        serde_json::from_value(json_resp).map_err(ApiError::ParseError)
+    }
+
+    // Helper functions to make the API calls more ergonomic
+    pub fn fetch_depth_data<'a>(
+        &'a self,
+        range: &BackfillRange,
+        asset: &Asset,
+    ) -> impl Stream<Item = Result<HistoryResponse<DepthInterval, DepthMeta>, ApiError>> + 'a {
+        let asset = asset.clone();
+        self.backfill_interval_data::<DepthInterval, DepthMeta>(format!("depths/{}", asset), range, None)
+            .map_ok(move |mut page| {
+                for interval in page.intervals.iter_mut() {
+                    interval.pool = Some(asset.clone());
+                }
+                page
+            })
+    }
+
+    // Network-wide swaps when `pool` is `None`, otherwise the swaps of that pool only
+    pub fn fetch_swaps_data<'a>(
+        &'a self,
+        range: &BackfillRange,
+        pool: Option<&Asset>,
+    ) -> impl Stream<Item = Result<HistoryResponse<SwapsInterval, SwapsMeta>, ApiError>> + 'a {
+        let tag = PoolScope::from(pool.cloned());
+        self.backfill_interval_data::<SwapsInterval, SwapsMeta>("swaps".to_string(), range, pool)
+            .map_ok(move |mut page| {
+                page.meta.pool = tag.clone();
+                for interval in page.intervals.iter_mut() {
+                    interval.pool = tag.clone();
+                }
+                page
+            })
+    }
+
+    pub fn fetch_earnings_data<'a>(
+        &'a self,
+        range: &BackfillRange,
+    ) -> impl Stream<Item = Result<HistoryResponse<EarningInterval, EarningsMeta>, ApiError>> + 'a {
+        self.backfill_interval_data("earnings".to_string(), range, None)
+    }
+
+    pub fn fetch_runepool_data<'a>(
+        &'a self,
+        range: &BackfillRange,
+    ) -> impl Stream<Item = Result<HistoryResponse<RunePoolInterval, RunePoolMeta>, ApiError>> + 'a {
+        self.backfill_interval_data("runepool".to_string(), range, None)
+    }
 
-    serde_json::from_value(serde_json::Value::Array(intervals.to_vec()))
-        .map_err(ApiError::ParseError)
+    pub fn fetch_tvl_data<'a>(
+        &'a self,
+        range: &BackfillRange,
+    ) -> impl Stream<Item = Result<HistoryResponse<TvlInterval, TvlMeta>, ApiError>> + 'a {
+        self.backfill_interval_data("tvl".to_string(), range, None)
+    }
+
+    // Fetches one page of actions and the token of the next (older) page, if any
+    pub async fn fetch_actions_page(
+        &self,
+        params: &ActionsParams,
+        page_token: Option<&str>,
+    ) -> Result<(Vec<Action>, Option<String>), ApiError> {
+        let mut path = format!("/actions?limit={}", MAX_ACTIONS_LIMIT);
+        let filters = [
+            ("fromTimestamp", params.from_timestamp.map(|from| from.to_string())),
+            ("type", params.action_type.clone()),
+            ("asset", params.asset.clone()),
+            ("address", params.address.clone()),
+            ("nextPageToken", page_token.map(str::to_string)),
+        ];
+        for (name, value) in filters {
+            if let Some(value) = value {
+                path.push_str(&format!("&{}={}", name, value));
+            }
+        }
+
+        parse_actions_page(self.get_json(&path).await?)
+    }
+
+    // Addresses of every liquidity provider, optionally limited to one pool
+    pub async fn fetch_members(&self, pool: Option<&Asset>) -> Result<Vec<String>, ApiError> {
+        let path = match pool {
+            Some(pool) => format!("/members?pool={}", pool),
+            None => "/members".to_string(),
+        };
+        let json_resp = self.get_json(&path).await?;
+        serde_json::from_value(json_resp).map_err(ApiError::ParseError)
+    }
+
+    // LP positions of `address` (rune or asset address) in every pool it is a member of
+    pub async fn fetch_member(&self, address: &str) -> Result<Vec<MemberPool>, ApiError> {
+        let Some(json_resp) = self.get_json_opt(&format!("/member/{}", address)).await? else {
+            return Ok(Vec::new());
+        };
+        parse_member(json_resp, address)
+    }
+
+    pub async fn fetch_runepool_provider(&self, address: &str) -> Result<Vec<RunePoolProvider>, ApiError> {
+        let Some(json_resp) = self.get_json_opt(&format!("/runepool/{}", address)).await? else {
+            return Ok(Vec::new());
+        };
+        parse_runepool_provider(json_resp, address)
+    }
+
+    pub async fn fetch_saver(&self, address: &str) -> Result<Vec<SaverPosition>, ApiError> {
+        let Some(json_resp) = self.get_json_opt(&format!("/saver/{}", address)).await? else {
+            return Ok(Vec::new());
+        };
+        parse_saver(json_resp, address)
+    }
 }
 
+// Response parsing, shared by the fetchers and by replays of archived responses
+
+// Intervals are parsed one by one: a malformed interval ends up in `rejected` with its raw
+// JSON instead of failing the page. `source` is the request path, recorded on rejects.
+// Every interval, parsed or not, is checked for schema drift.
+pub(crate) fn parse_history_page<T: DriftChecked, M: DeserializeOwned>(
+    mut json_resp: serde_json::Value,
+    source: &str,
+    drift: &SchemaDrift,
+) -> Result<HistoryResponse<T, M>, ApiError> {
+    let meta = json_resp.get_mut("meta")
+        .map(serde_json::Value::take)
+        .ok_or_else(|| ApiError::InvalidResponse("No meta found in response".to_string()))?;
+    let meta = serde_json::from_value(meta).map_err(ApiError::ParseError)?;
 
-// Helper functions to make the API calls more ergonomic
-pub async fn fetch_depth_data(
-    params: &IntervalParams,
-    asset: &str,
-) -> Result<Vec<DepthInterval>, ApiError> {
-    fetch_interval_data(&format!("depths/{}", asset), params).await
+    let Some(serde_json::Value::Array(raw_intervals)) = json_resp.get_mut("intervals").map(serde_json::Value::take) else {
+        return Err(ApiError::InvalidResponse("No intervals found in response".to_string()));
+    };
+
+    let rejected_at = chrono::Utc::now().timestamp();
+    let mut intervals = Vec::with_capacity(raw_intervals.len());
+    let mut rejected = Vec::new();
+    for raw in raw_intervals {
+        match T::deserialize(&raw) {
+            Ok(mut interval) => {
+                drift.inspect(&raw, Some(&mut interval));
+                intervals.push(interval);
+            }
+            Err(e) => {
+                drift.inspect::<T>(&raw, None);
+                rejected.push(DeadLetter { source: source.to_string(), error: e.to_string(), raw, rejected_at });
+            }
+        }
+    }
+
+    Ok(HistoryResponse { meta, intervals, rejected })
 }
 
-pub async fn fetch_swaps_data(
-    params: &IntervalParams,
-) -> Result<Vec<SwapsInterval>, ApiError> {
-    fetch_interval_data("swaps", params).await
+// Actions of one page and the token of the next (older) page, if any
+pub(crate) fn parse_actions_page(json_resp: serde_json::Value) -> Result<(Vec<Action>, Option<String>), ApiError> {
+    let actions = json_resp["actions"]
+        .as_array()
+        .ok_or_else(|| ApiError::InvalidResponse("No actions found in response".to_string()))?;
+    let mut actions: Vec<Action> = serde_json::from_value(serde_json::Value::Array(actions.to_vec()))
+        .map_err(ApiError::ParseError)?;
+    for action in actions.iter_mut() {
+        action.tx_id = action.key();
+    }
+
+    let next_page_token = json_resp["meta"]["nextPageToken"]
+        .as_str()
+        .filter(|token| !token.is_empty())
+        .map(str::to_string);
+
+    Ok((actions, next_page_token))
 }
 
-pub async fn fetch_earnings_data(
-    params: &IntervalParams,
-) -> Result<Vec<EarningInterval>, ApiError> {
-    fetch_interval_data("earnings", params).await
+pub(crate) fn parse_member(json_resp: serde_json::Value, address: &str) -> Result<Vec<MemberPool>, ApiError> {
+    let pools = json_resp["pools"]
+        .as_array()
+        .ok_or_else(|| ApiError::InvalidResponse("No pools found in member response".to_string()))?;
+    let mut positions: Vec<MemberPool> = serde_json::from_value(serde_json::Value::Array(pools.to_vec()))
+        .map_err(ApiError::ParseError)?;
+    for position in positions.iter_mut() {
+        position.member_address = address.to_string();
+    }
+    Ok(positions)
 }
 
-pub async fn fetch_runepool_data(
-    params: &IntervalParams,
-) -> Result<Vec<RunePoolInterval>, ApiError> {
-    fetch_interval_data("runepool", params).await
-}
\ No newline at end of file
+pub(crate) fn parse_runepool_provider(json_resp: serde_json::Value, address: &str) -> Result<Vec<RunePoolProvider>, ApiError> {
+    let mut providers: Vec<RunePoolProvider> = serde_json::from_value(json_resp).map_err(ApiError::ParseError)?;
+    for provider in providers.iter_mut() {
+        provider.member_address = address.to_string();
+    }
+    Ok(providers)
+}
+
+pub(crate) fn parse_saver(json_resp: serde_json::Value, address: &str) -> Result<Vec<SaverPosition>, ApiError> {
+    let pools = json_resp["pools"]
+        .as_array()
+        .ok_or_else(|| ApiError::InvalidResponse("No pools found in saver response".to_string()))?;
+    let mut positions: Vec<SaverPosition> = serde_json::from_value(serde_json::Value::Array(pools.to_vec()))
+        .map_err(ApiError::ParseError)?;
+    for position in positions.iter_mut() {
+        position.member_address = address.to_string();
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use std::{collections::HashMap, sync::Mutex};
+
+    use axum::{extract::Query, http::StatusCode, routing::get, Json, Router};
+    use futures::TryStreamExt;
+    use serde_json::json;
+
+    use super::{parse_history_page, ApiConfig, ApiError, BackfillRange, MidgardClient, RateLimitConfig, RateLimiter, RetryConfig};
+    use crate::{api::schema_drift::SchemaDrift, models::{Interval, RunePoolInterval, RunePoolMeta, TimeWindow}};
+
+    // Serves `router` on a free local port and returns its base URL
+    fn serve(router: Router) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(router.into_make_service()));
+        url
+    }
+
+    // Mirror answering every request with `status`, counting the requests it got
+    fn mirror(status: StatusCode) -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let router = Router::new().fallback(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move { (status, Json(json!({ "ok": status.is_success() }))) }
+        });
+        (serve(router), hits)
+    }
+
+    fn client(base_urls: Vec<String>) -> MidgardClient {
+        MidgardClient::new(ApiConfig {
+            base_urls,
+            retry: RetryConfig { max_attempts: 1, ..RetryConfig::default() },
+            rate_limit: None,
+            ..ApiConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn retry(jitter: bool) -> RetryConfig {
+        RetryConfig { max_attempts: 5, base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(1), jitter }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let retry = retry(false);
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(4), Duration::from_millis(800));
+        assert_eq!(retry.backoff(5), Duration::from_secs(1));
+        assert_eq!(retry.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn jittered_backoff_stays_within_half_to_full_delay() {
+        let retry = retry(true);
+        for _ in 0..100 {
+            let delay = retry.backoff(3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn retry_after_is_honoured_up_to_max_delay() {
+        let retry = retry(false);
+        assert_eq!(retry.retry_delay(1, Some(Duration::from_millis(300))), Duration::from_millis(300));
+        assert_eq!(retry.retry_delay(1, Some(Duration::from_secs(3600))), Duration::from_secs(1));
+        assert_eq!(retry.retry_delay(3, None), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn dead_letters_intervals_that_do_not_parse() {
+        let body = json!({
+            "meta": { "endCount": "2", "endTime": "7200", "endUnits": "20", "startCount": "1", "startTime": "0", "startUnits": "10" },
+            "intervals": [
+                { "count": "1", "endTime": "3600", "startTime": "0", "units": "10" },
+                { "count": "many", "endTime": "7200", "startTime": "3600", "units": "20" },
+            ],
+        });
+        let page = parse_history_page::<RunePoolInterval, RunePoolMeta>(body, "/v2/history/runepool", &SchemaDrift::new(false)).unwrap();
+
+        assert_eq!(page.intervals.len(), 1);
+        assert_eq!(page.intervals[0].end_time, 3600);
+        assert_eq!(page.rejected.len(), 1);
+        assert_eq!(page.rejected[0].source, "/v2/history/runepool");
+        assert_eq!(page.rejected[0].raw["count"], "many");
+    }
+
+    #[test]
+    fn rejects_pages_without_meta_or_intervals() {
+        let drift = SchemaDrift::new(false);
+        assert!(parse_history_page::<RunePoolInterval, RunePoolMeta>(json!({ "intervals": [] }), "", &drift).is_err());
+        let meta = json!({ "endCount": "0", "endTime": "0", "endUnits": "0", "startCount": "0", "startTime": "0", "startUnits": "0" });
+        assert!(parse_history_page::<RunePoolInterval, RunePoolMeta>(json!({ "meta": meta }), "", &drift).is_err());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_lets_a_burst_through() {
+        let limiter = RateLimiter::new(&RateLimitConfig { requests_per_second: 1.0, burst: 3 });
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(100), "{:?}", start.elapsed());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_waits_once_the_burst_is_spent() {
+        let limiter = RateLimiter::new(&RateLimitConfig { requests_per_second: 20.0, burst: 1 });
+        limiter.acquire().await;
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        // Two more tokens at 20 per second
+        assert!(start.elapsed() >= Duration::from_millis(90), "{:?}", start.elapsed());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_refills_up_to_the_burst() {
+        let limiter = RateLimiter::new(&RateLimitConfig { requests_per_second: 20.0, burst: 2 });
+        limiter.acquire().await;
+        limiter.acquire().await;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(25), "{:?}", start.elapsed());
+        // Idle time beyond the burst is not banked
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(40), "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn mirrors_cooling_down_are_skipped_then_ordered_by_recovery() {
+        let client = client(vec!["http://a".to_string(), "http://b".to_string(), "http://c".to_string()]);
+        assert_eq!(client.mirror_order(), [0, 1, 2]);
+
+        client.mark_mirror(1, false);
+        assert_eq!(client.mirror_order(), [0, 2]);
+
+        std::thread::sleep(Duration::from_millis(2));
+        client.mark_mirror(0, false);
+        std::thread::sleep(Duration::from_millis(2));
+        client.mark_mirror(2, false);
+        assert_eq!(client.mirror_order(), [1, 0, 2]);
+
+        client.mark_mirror(2, true);
+        assert_eq!(client.mirror_order(), [2]);
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_the_next_mirror() {
+        let (down, down_hits) = mirror(StatusCode::SERVICE_UNAVAILABLE);
+        let (up, up_hits) = mirror(StatusCode::OK);
+        let client = client(vec![down, up]);
+
+        assert_eq!(client.get_json("/pools").await.unwrap(), json!({ "ok": true }));
+        assert_eq!(client.mirror_order(), [1]);
+        // The failed mirror is cooling down, so the next request skips it
+        client.get_json("/pools").await.unwrap();
+        assert_eq!(down_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(up_hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_a_mirror_is_unreachable() {
+        let unreachable = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let (up, _) = mirror(StatusCode::OK);
+        let client = client(vec![unreachable, up]);
+
+        assert!(client.get_json("/pools").await.is_ok());
+        assert_eq!(client.mirror_order(), [1]);
+    }
+
+    #[tokio::test]
+    async fn client_errors_do_not_fail_over() {
+        let (bad_request, _) = mirror(StatusCode::BAD_REQUEST);
+        let (up, up_hits) = mirror(StatusCode::OK);
+        let client = client(vec![bad_request, up]);
+
+        assert!(matches!(client.get_json("/pools").await, Err(ApiError::ClientError { .. })));
+        assert_eq!(up_hits.load(Ordering::SeqCst), 0);
+        assert_eq!(client.mirror_order(), [0, 1]);
+    }
+
+    // `/history/runepool` answering with two hourly intervals from `from`, stopping at `latest`
+    // like Midgard stops at the current interval; records the `from` of every request
+    fn runepool_history(latest: i64) -> (String, Arc<Mutex<Vec<i64>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let router = Router::new().route(
+            "/history/runepool",
+            get(move |Query(query): Query<HashMap<String, String>>| {
+                let from: i64 = query["from"].parse().unwrap();
+                seen.lock().unwrap().push(from);
+                let end = (from + 7200).min(latest);
+                let intervals: Vec<_> = (from..end)
+                    .step_by(3600)
+                    .map(|start| json!({ "count": "1", "startTime": start.to_string(), "endTime": (start + 3600).to_string(), "units": "1" }))
+                    .collect();
+                let meta = json!({
+                    "startTime": from.to_string(), "endTime": end.to_string(),
+                    "startCount": "1", "endCount": "1", "startUnits": "1", "endUnits": "1",
+                });
+                async move { Json(json!({ "meta": meta, "intervals": intervals })) }
+            }),
+        );
+        (serve(router), requests)
+    }
+
+    async fn backfill(client: &MidgardClient, start: i64, end: i64) -> Vec<Vec<i64>> {
+        let range = BackfillRange { window: TimeWindow::new(start, end).unwrap(), interval: Interval::Hour };
+        client
+            .backfill_interval_data::<RunePoolInterval, RunePoolMeta>("runepool".to_string(), &range, None)
+            .map_ok(|page| page.intervals.iter().map(|interval| interval.end_time).collect())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn backfill_continues_from_each_page_end_and_stops_at_the_window_end() {
+        let (url, requests) = runepool_history(i64::MAX);
+        let pages = backfill(&client(vec![url]), 0, 5 * 3600).await;
+
+        assert_eq!(*requests.lock().unwrap(), [0, 7200, 14400]);
+        // The interval closing after the window is left for the next run
+        assert_eq!(pages, [vec![3600, 7200], vec![10800, 14400], vec![18000]]);
+    }
+
+    #[tokio::test]
+    async fn backfill_stops_once_midgard_has_nothing_newer() {
+        let (url, requests) = runepool_history(3 * 3600);
+        let pages = backfill(&client(vec![url]), 0, 10 * 3600).await;
+
+        // The page that makes no progress is still yielded, and is the last one
+        assert_eq!(*requests.lock().unwrap(), [0, 7200, 10800]);
+        assert_eq!(pages, [vec![3600, 7200], vec![10800], vec![]]);
+    }
+
+    #[tokio::test]
+    async fn backfill_of_an_empty_window_fetches_nothing() {
+        let (url, requests) = runepool_history(i64::MAX);
+        assert!(backfill(&client(vec![url]), 3600, 3600).await.is_empty());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+}
@@ -1 +1,3 @@
-pub mod api_fetcher;
\ No newline at end of file
+pub mod api_fetcher;
+pub mod response_archive;
+pub mod schema_drift;
\ No newline at end of file
@@ -0,0 +1,107 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+// Extension of archived responses: snappy framed JSON
+const ARCHIVE_EXTENSION: &str = "json.sz";
+
+// Raw Midgard response as it was received, together with the request that produced it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedResponse {
+    // Request path relative to the base URL, including the query string
+    pub path: String,
+    // Unix milliseconds
+    pub fetched_at: i64,
+    pub body: serde_json::Value,
+}
+
+impl ArchivedResponse {
+    // Path without the query string, e.g. `/history/depths/BTC.BTC`
+    pub fn endpoint(&self) -> &str {
+        self.path.split_once('?').map_or(self.path.as_str(), |(endpoint, _)| endpoint)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        let (_, query) = self.path.split_once('?')?;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+// Compressed copies of every raw response, laid out as
+// `{dir}/{endpoint}/{fetched_at}_{params}.json.sz` so a fetch can be found by hand
+#[derive(Debug, Clone)]
+pub struct ResponseArchive {
+    dir: PathBuf,
+}
+
+// Keeps names portable: anything but alphanumerics, `.`, `-` and `=` becomes `_`
+fn sanitize(raw: &str) -> String {
+    raw.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '=') { c } else { '_' })
+        .collect()
+}
+
+impl ResponseArchive {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub async fn write(&self, path: &str, body: &serde_json::Value) -> io::Result<PathBuf> {
+        let fetched_at = chrono::Utc::now().timestamp_millis();
+        let (endpoint, params) = path.split_once('?').unwrap_or((path, ""));
+
+        let endpoint_dir = self.dir.join(sanitize(endpoint.trim_start_matches('/')));
+        tokio::fs::create_dir_all(&endpoint_dir).await?;
+
+        let response = ArchivedResponse { path: path.to_string(), fetched_at, body: body.clone() };
+        let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+        serde_json::to_writer(&mut encoder, &response)?;
+        let compressed = encoder.into_inner().map_err(|e| e.into_error())?;
+
+        let file = endpoint_dir.join(format!("{}_{}.{}", fetched_at, sanitize(params), ARCHIVE_EXTENSION));
+        tokio::fs::write(&file, compressed).await?;
+        Ok(file)
+    }
+
+    pub async fn read(&self, file: &Path) -> io::Result<ArchivedResponse> {
+        let compressed = tokio::fs::read(file).await?;
+        let mut json = Vec::new();
+        snap::read::FrameDecoder::new(compressed.as_slice()).read_to_end(&mut json)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    // Every archived file, oldest fetch first
+    pub async fn list(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut endpoints = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(endpoint) = endpoints.next_entry().await? {
+            if !endpoint.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = tokio::fs::read_dir(endpoint.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let file = entry.path();
+                if file.to_string_lossy().ends_with(ARCHIVE_EXTENSION) {
+                    files.push(file);
+                }
+            }
+        }
+
+        let fetched_at = |file: &PathBuf| -> i64 {
+            file.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('_').next())
+                .and_then(|millis| millis.parse().ok())
+                .unwrap_or(0)
+        };
+        files.sort_by_key(fetched_at);
+        Ok(files)
+    }
+}
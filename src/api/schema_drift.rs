@@ -0,0 +1,271 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+use crate::models::{DepthInterval, EarningInterval, Pool, RunePoolInterval, SwapsInterval, TvlInterval};
+
+// Models whose Midgard JSON is compared against the fields they know about
+pub trait DriftChecked: DeserializeOwned {
+    // Fields filled in locally rather than read from Midgard
+    const LOCAL_FIELDS: &'static [&'static str] = &["extra"];
+
+    fn set_extra(&mut self, extra: serde_json::Value);
+
+    // Checks models nested in this one, e.g. the per-pool entries of an earnings interval
+    fn check_nested(&mut self, _raw: &serde_json::Value, _drift: &SchemaDrift) {}
+}
+
+impl DriftChecked for DepthInterval {
+    const LOCAL_FIELDS: &'static [&'static str] = &["pool", "granularity", "extra"];
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+}
+
+impl DriftChecked for SwapsInterval {
+    const LOCAL_FIELDS: &'static [&'static str] = &["pool", "granularity", "extra"];
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+}
+
+impl DriftChecked for EarningInterval {
+    const LOCAL_FIELDS: &'static [&'static str] = &["granularity", "extra"];
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+
+    fn check_nested(&mut self, raw: &serde_json::Value, drift: &SchemaDrift) {
+        let raw_pools = raw["pools"].as_array().into_iter().flatten();
+        for (pool, raw_pool) in self.pools.iter_mut().zip(raw_pools) {
+            drift.inspect(raw_pool, Some(pool));
+        }
+    }
+}
+
+impl DriftChecked for Pool {
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+}
+
+impl DriftChecked for RunePoolInterval {
+    const LOCAL_FIELDS: &'static [&'static str] = &["granularity", "extra"];
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+}
+
+impl DriftChecked for TvlInterval {
+    const LOCAL_FIELDS: &'static [&'static str] = &["granularity", "extra"];
+    fn set_extra(&mut self, extra: serde_json::Value) { self.extra = Some(extra) }
+}
+
+// Field names (after renames) that the derived `Deserialize` of `T` asks for
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldsDeserializer<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldsDeserializer<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields captured"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldsDeserializer(&mut fields));
+    fields
+}
+
+fn model_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+// Fields of one model that responses disagree on
+#[derive(Debug, Clone, Default)]
+pub struct DriftReport {
+    // In responses but unknown to the model
+    pub new_fields: BTreeSet<String>,
+    // Known to the model but absent from responses
+    pub missing_fields: BTreeSet<String>,
+}
+
+// Compares raw Midgard records with the models they are parsed into. Each new or missing
+// field is reported once when first seen and again in the summary at the end of the run.
+#[derive(Debug, Default)]
+pub struct SchemaDrift {
+    // Keep unknown fields in the model's `extra` map so they are stored with the record
+    capture_unknown: bool,
+    reports: Mutex<BTreeMap<&'static str, DriftReport>>,
+}
+
+impl SchemaDrift {
+    pub fn new(capture_unknown: bool) -> Self {
+        Self { capture_unknown, ..Self::default() }
+    }
+
+    // Checks the keys of `raw` against `T`. `record` is the parsed model, if parsing succeeded,
+    // and receives the unknown fields when capturing is enabled.
+    pub fn inspect<T: DriftChecked>(&self, raw: &serde_json::Value, record: Option<&mut T>) {
+        let Some(object) = raw.as_object() else { return };
+        let model = model_name::<T>();
+        let known: Vec<&str> = struct_fields::<T>()
+            .iter()
+            .copied()
+            .filter(|field| !T::LOCAL_FIELDS.contains(field))
+            .collect();
+
+        let unknown: serde_json::Map<String, serde_json::Value> = object
+            .iter()
+            .filter(|(key, _)| !known.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let missing = known.iter().filter(|field| !object.contains_key(**field));
+
+        {
+            let mut reports = self.reports.lock().unwrap();
+            let report = reports.entry(model).or_default();
+            for field in unknown.keys() {
+                if report.new_fields.insert(field.clone()) {
+                    eprintln!("Schema drift in {}: new field `{}`", model, field);
+                }
+            }
+            for field in missing {
+                if report.missing_fields.insert(field.to_string()) {
+                    eprintln!("Schema drift in {}: missing field `{}`", model, field);
+                }
+            }
+        }
+
+        if let Some(record) = record {
+            if self.capture_unknown && !unknown.is_empty() {
+                record.set_extra(serde_json::Value::Object(unknown));
+            }
+            record.check_nested(raw, self);
+        }
+    }
+
+    // Models with at least one new or missing field
+    pub fn reports(&self) -> BTreeMap<&'static str, DriftReport> {
+        self.reports
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, report)| !report.new_fields.is_empty() || !report.missing_fields.is_empty())
+            .map(|(model, report)| (*model, report.clone()))
+            .collect()
+    }
+
+    pub fn print_summary(&self) {
+        let reports = self.reports();
+        if reports.is_empty() {
+            println!("NO SCHEMA DRIFT DETECTED");
+            return;
+        }
+        println!("SCHEMA DRIFT DETECTED:");
+        for (model, report) in reports {
+            if !report.new_fields.is_empty() {
+                println!("  {} new fields: {}", model, report.new_fields.iter().cloned().collect::<Vec<_>>().join(", "));
+            }
+            if !report.missing_fields.is_empty() {
+                println!("  {} missing fields: {}", model, report.missing_fields.iter().cloned().collect::<Vec<_>>().join(", "));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_json::{json, Value};
+
+    use super::SchemaDrift;
+    use crate::models::{EarningInterval, RunePoolInterval};
+
+    fn runepool_interval() -> Value {
+        json!({ "count": "1", "startTime": "0", "endTime": "3600", "units": "10" })
+    }
+
+    fn parse(raw: &Value) -> RunePoolInterval {
+        serde_json::from_value(raw.clone()).unwrap()
+    }
+
+    fn fields(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    // `granularity` and `extra` are never sent, and are not reported missing
+    #[test]
+    fn matching_records_report_no_drift() {
+        let drift = SchemaDrift::new(true);
+        let raw = runepool_interval();
+        let mut record = parse(&raw);
+        drift.inspect(&raw, Some(&mut record));
+
+        assert!(drift.reports().is_empty());
+        assert!(record.extra.is_none());
+    }
+
+    #[test]
+    fn reports_new_and_missing_fields() {
+        let drift = SchemaDrift::new(false);
+        let mut raw = runepool_interval();
+        raw["depositors"] = json!("4");
+        raw.as_object_mut().unwrap().remove("count");
+        drift.inspect::<RunePoolInterval>(&raw, None);
+
+        let report = &drift.reports()["RunePoolInterval"];
+        assert_eq!(report.new_fields, fields(&["depositors"]));
+        assert_eq!(report.missing_fields, fields(&["count"]));
+    }
+
+    #[test]
+    fn new_fields_are_captured_only_when_enabled() {
+        let mut raw = runepool_interval();
+        raw["depositors"] = json!("4");
+
+        let mut record = parse(&raw);
+        SchemaDrift::new(false).inspect(&raw, Some(&mut record));
+        assert!(record.extra.is_none());
+
+        let mut record = parse(&raw);
+        SchemaDrift::new(true).inspect(&raw, Some(&mut record));
+        assert_eq!(record.extra, Some(json!({ "depositors": "4" })));
+    }
+
+    #[test]
+    fn nested_pools_are_checked_and_captured() {
+        let raw = json!({
+            "avgNodeCount": "100", "blockRewards": "1", "bondingEarnings": "1", "earnings": "2",
+            "startTime": "0", "endTime": "3600", "liquidityEarnings": "1", "liquidityFees": "1", "runePriceUSD": "5",
+            "pools": [{
+                "assetLiquidityFees": "1", "earnings": "1", "pool": "BTC.BTC", "rewards": "1", "runeLiquidityFees": "1",
+                "saverEarning": "0", "totalLiquidityFeesRune": "1", "lendingEarnings": "3",
+            }],
+        });
+        let mut record: EarningInterval = serde_json::from_value(raw.clone()).unwrap();
+        let drift = SchemaDrift::new(true);
+        drift.inspect(&raw, Some(&mut record));
+
+        let reports = drift.reports();
+        assert!(!reports.contains_key("EarningInterval"));
+        assert_eq!(reports["Pool"].new_fields, fields(&["lendingEarnings"]));
+        assert!(record.extra.is_none());
+        assert_eq!(record.pools[0].extra, Some(json!({ "lendingEarnings": "3" })));
+    }
+}
@@ -1,8 +1,9 @@
-use std::env;
+use std::{env, time::Duration};
 
-use api::api_fetcher::{fetch_depth_data, fetch_earnings_data, fetch_runepool_data, fetch_swaps_data, IntervalParams};
+use api::{api_fetcher::{ActionsParams, ApiConfig, BackfillRange, MidgardClient}, response_archive::ResponseArchive};
 use dotenv::dotenv;
-use services::db_factory::{match_database_type, DatabaseFactory}; 
+use models::{Asset, AssetKind, Interval, TimeWindow};
+use services::{db_factory::{match_database_type, DatabaseFactory}, daemon::{Daemon, Schedule}, ingester::{Ingester, Series}, member_snapshotter::{MemberSnapshotter, MemberWatchlist}, replayer::Replayer, shutdown::Shutdown, db_traits::Database}; 
 mod models;
 mod api;
 mod services;
@@ -12,6 +13,16 @@ mod repositories;
 async fn main() {
     dotenv().ok();
 
+    let config = ApiConfig::from_env();
+    let archive_dir = config.archive_dir.clone();
+    let midgard = match MidgardClient::new(config) {
+        Ok(midgard) => midgard,
+        Err(e) => {
+            eprintln!("Failed to build Midgard client: {}", e);
+            return;
+        }
+    };
+
     // let mongo_db_name = String::from("thor_api");
     // let mongodb_url = env::var("MONGODB_URI").expect("MONGODB_URI must be set");
     // let db = match match_database_type("mongodb",&[mongodb_url , mongo_db_name]) {
@@ -51,57 +62,151 @@ async fn main() {
 
     println!("DATABASE CONNECTED SUCCESSFULLY!");
 
-    let params = IntervalParams {
-        from: 1726758000,
-        count: 1,
-        interval: "hour".to_string(),
-    };
+    // On SIGINT/SIGTERM new fetches stop and in-flight writes get SHUTDOWN_TIMEOUT_SECS to finish
+    let shutdown = Shutdown::from_env();
+    {
+        let work = run(&midgard, db.as_ref(), archive_dir, &shutdown);
+        tokio::pin!(work);
+        tokio::select! {
+            _ = &mut work => {},
+            _ = shutdown.listen_for_signals() => {
+                if tokio::time::timeout(shutdown.timeout, &mut work).await.is_err() {
+                    eprintln!("Gave up waiting after {:?} with {} writes in flight", shutdown.timeout, shutdown.in_flight());
+                }
+            }
+        }
+    }
 
-    // Fetch depth data
-    let depth_data = match fetch_depth_data(&params, "BTC.BTC").await {
-        Ok(depth_data) => depth_data,
-        Err(e) => {eprintln!("Failed to fetch depth data: {}", e); return;},
-    };
+    if let Err(e) = db.close().await {
+        eprintln!("Failed to close the database: {}", e);
+    }
+    println!("DATABASE CLOSED");
+}
 
-    for interval in depth_data {
-        let duration = db.store_depth_intervals(interval).await;
-        println!("Inserted depth intervals in {:?}",duration);
+async fn run(midgard: &MidgardClient, db: &dyn Database, archive_dir: Option<String>, shutdown: &Shutdown) {
+    // `replay` loads the archived responses in MIDGARD_ARCHIVE_DIR instead of fetching anything
+    if env::args().nth(1).as_deref() == Some("replay") {
+        let Some(archive_dir) = archive_dir else {
+            eprintln!("MIDGARD_ARCHIVE_DIR must be set to replay archived responses");
+            return;
+        };
+        if let Err(e) = Replayer::new(db, ResponseArchive::new(archive_dir), midgard.schema_drift(), shutdown).replay().await {
+            eprintln!("Failed to replay archived responses: {}", e);
+        }
+        midgard.schema_drift().print_summary();
+        return;
     }
-    println!("DEPTH DATA INSERTED SUCCESSFULLY!");
 
-    // Fetch swaps data
-    let swaps_data = match fetch_swaps_data(&params).await {
-        Ok(swaps_data) => swaps_data,
-        Err(e) => { eprintln!("Failed to fetch swap data: {}", e); return; },
+    // Backfill window, defaults to everything from the original start time up to now.
+    // Each series resumes from its stored checkpoint when one is ahead of `from`.
+    let window = match TimeWindow::new(
+        env::var("BACKFILL_FROM").ok().and_then(|v| v.parse().ok()).unwrap_or(1726758000),
+        env::var("BACKFILL_TO").ok().and_then(|v| v.parse().ok()).unwrap_or_else(|| chrono::Utc::now().timestamp()),
+    ) {
+        Ok(window) => window,
+        Err(e) => { eprintln!("Invalid BACKFILL_FROM/BACKFILL_TO: {}", e); return; },
     };
-
-    for interval in swaps_data {
-        let duration = db.store_swaps_intervals(interval).await;
-        println!("Inserted swap intervals in {:?}",duration);
+    let interval = match env::var("BACKFILL_INTERVAL").map_or(Ok(Interval::Hour), |v| v.parse()) {
+        Ok(interval) => interval,
+        Err(e) => { eprintln!("Invalid BACKFILL_INTERVAL: {}", e); return; },
+    };
+    let range = BackfillRange { window, interval };
+
+    // Pools listed in POOLS, otherwise every pool Midgard knows about
+    let pool_list = match env::var("POOLS").ok().map(|assets| {
+        assets.split(',').map(str::trim).filter(|asset| !asset.is_empty()).map(str::parse).collect::<Result<Vec<Asset>, _>>()
+    }).transpose() {
+        Ok(pools) => pools,
+        Err(e) => { eprintln!("Invalid POOLS: {}", e); return; },
+    };
+    // Pools are named by their native asset; synth, trade and secured assets have no pool of their own
+    if let Some(asset) = pool_list.iter().flatten().find(|asset| asset.kind() != AssetKind::Native) {
+        eprintln!("Invalid POOLS: {} is not a pool asset", asset);
+        return;
     }
-    println!("SWAP DATA INSERTED SUCCESSFULLY!");
 
-    // Fetch earnings data
-    let earnings_data = match fetch_earnings_data(&params).await {
-        Ok(earnings_data) => earnings_data,
-        Err(e) => { eprintln!("Failed to fetch earnings data: {}", e); return; },
+    // Period of the member snapshots taken after the backfill; they are taken once when unset
+    let snapshot_every = match env::var("MEMBER_SNAPSHOT_EVERY_SECS").ok().map(|v| v.parse::<u64>()) {
+        None => None,
+        Some(Ok(secs)) if secs > 0 => Some(Duration::from_secs(secs)),
+        Some(_) => { eprintln!("Invalid MEMBER_SNAPSHOT_EVERY_SECS: expected a whole number of seconds above 0"); return; },
     };
 
-    for interval in earnings_data {
-        let duration = db.store_earnings_intervals(interval).await;
-        println!("Inserted earnings intervals in {:?}",duration);
+    // Series fetched at once, each independent of the others
+    let concurrency = env::var("INGEST_CONCURRENCY").ok().and_then(|v| v.parse().ok()).unwrap_or(4);
+
+    // `daemon` keeps polling the history series on interval boundaries instead of running once
+    if env::args().nth(1).as_deref() == Some("daemon") {
+        let schedules = match Schedule::from_env() {
+            Ok(schedules) => schedules,
+            Err(e) => { eprintln!("Invalid daemon configuration: {}", e); return; },
+        };
+        let ingester = Ingester::new(midgard, db, shutdown, concurrency);
+        Daemon::new(midgard, &ingester, range.window.start(), pool_list, shutdown).run(&schedules).await;
+        return;
     }
-    println!("EARNING DATA INSERTED SUCCESSFULLY!");
 
-    // Fetch rune pool data
-    let runepool_data = match fetch_runepool_data(&params).await {
-        Ok(runepool_data) => runepool_data,
-        Err(e) => { eprintln!("Failed to fetch rune pool data: {}", e); return; },
+    // Snapshot current pool state
+    let pools = match &pool_list {
+        Some(assets) => {
+            let mut pools = Vec::new();
+            for asset in assets {
+                match midgard.fetch_pool(asset).await {
+                    Ok(pool) => pools.push(pool),
+                    Err(e) => eprintln!("Failed to fetch pool {}: {}", asset, e),
+                }
+            }
+            pools
+        }
+        None => match midgard.fetch_pools().await {
+            Ok(pools) => pools,
+            Err(e) => { eprintln!("Failed to fetch pool list: {}", e); return; },
+        },
     };
 
-    for interval in runepool_data {
-        let duration = db.store_runepool_intervals(interval).await;
-        println!("Inserted runepool intervals in {:?}",duration);
+    let snapshot_time = chrono::Utc::now().timestamp();
+    let mut assets = Vec::new();
+    for mut pool in pools {
+        pool.snapshot_time = snapshot_time;
+        assets.push(pool.asset.clone());
+        let duration = db.store_pool_snapshot(pool).await;
+        println!("Inserted pool snapshot in {:?}",duration);
+    }
+    println!("POOL SNAPSHOTS INSERTED SUCCESSFULLY!");
+
+    let ingester = Ingester::new(midgard, db, shutdown, concurrency);
+
+    // Depth of every pool, network-wide swaps and the swaps of every pool, plus the network series
+    let mut series: Vec<Series> = assets.iter().cloned().map(Series::Depth).collect();
+    series.push(Series::Swaps(None));
+    series.extend(assets.iter().cloned().map(|asset| Series::Swaps(Some(asset))));
+    series.extend([Series::Earnings, Series::RunePool, Series::Tvl]);
+
+    let report = ingester.ingest_all(&series, &range).await;
+
+    // Individual actions are opt-in, there are far more of them than intervals
+    if env::var("INGEST_ACTIONS").is_ok_and(|v| v == "true") && !shutdown.is_requested() {
+        let params = ActionsParams {
+            action_type: env::var("ACTIONS_TYPE").ok(),
+            ..ActionsParams::default()
+        };
+        match ingester.ingest_actions(params, range.window.start_time()).await {
+            Ok(stored) => println!("{} ACTIONS INSERTED", stored),
+            Err(e) => eprintln!("Failed to ingest actions: {}", e),
+        }
+    }
+
+    report.print_summary();
+    ingester.print_rejected_summary();
+    midgard.schema_drift().print_summary();
+
+    // LP position snapshots for the watchlist, once or on a schedule when MEMBER_SNAPSHOT_EVERY_SECS is set
+    let watchlist = MemberWatchlist::from_env();
+    if !watchlist.is_empty() && !shutdown.is_requested() {
+        let snapshotter = MemberSnapshotter::new(midgard, db, watchlist);
+        match snapshot_every {
+            Some(every) => snapshotter.run(every, shutdown).await,
+            None => { snapshotter.snapshot().await; },
+        }
     }
-    println!("RUNEPOOL DATA INSERTED SUCCESSFULLY!");
 }
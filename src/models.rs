@@ -1,42 +1,100 @@
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
+
+mod amount;
+mod asset;
+mod count;
+mod finite;
+mod interval;
+pub use amount::{with_native_amounts, Amount};
+pub use asset::{AnyAsset, Asset, AssetKind, PoolScope, ALL_POOLS};
+pub use count::Count;
+pub use finite::Finite;
+pub use interval::{Interval, TimeWindow, WindowError, MAX_INTERVAL_COUNT};
 // use sqlx::prelude::FromRow;
 
 #[serde_as]
-#[derive(Debug , Serialize , Deserialize )]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DepthInterval {
-    #[serde_as(as = "DisplayFromStr")]
-    pub asset_depth: i64,
-    #[serde_as(as = "DisplayFromStr")]
+    // Not part of the Midgard response, filled in from the requested asset
+    #[serde(default)]
+    pub pool: Option<Asset>,
+    // Not part of the Midgard response, filled in from the requested interval
+    #[serde(default)]
+    pub granularity: Interval,
+    pub asset_depth: Amount,
+    #[serde_as(as = "Finite")]
     pub asset_price: f64,
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     #[serde(rename = "assetPriceUSD")]
     pub asset_price_usd: f64 ,
     #[serde_as(as = "DisplayFromStr")]
     pub end_time: i64,
-    #[serde_as(as = "DisplayFromStr")]
-    pub liquidity_units: i64,
-    #[serde_as(as = "DisplayFromStr")]
+    pub liquidity_units: Amount,
+    #[serde_as(as = "Finite")]
     pub luvi: f64,
     #[serde_as(as = "DisplayFromStr")]
     pub members_count: i64,
-    #[serde_as(as = "DisplayFromStr")]
-    pub rune_depth: i64,
+    pub rune_depth: Amount,
     #[serde_as(as = "DisplayFromStr")]
     pub start_time: i64,
+    pub synth_supply: Amount,
+    pub synth_units: Amount,
+    pub units: Amount,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+// `meta` block of `/history/depths/{pool}`: pool state at the start and end of the requested window.
+// Optional fields were added to Midgard over time and may be missing on older deployments.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthMeta {
     #[serde_as(as = "DisplayFromStr")]
-    pub synth_supply: i64,
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_units: i64,
+    pub start_time: i64,
     #[serde_as(as = "DisplayFromStr")]
-    pub units: i64,
+    pub end_time: i64,
+    #[serde_as(as = "Option<Finite>")]
+    #[serde(default)]
+    pub price_shift_loss: Option<f64>,
+    #[serde_as(as = "Option<Finite>")]
+    #[serde(default)]
+    pub luvi_increase: Option<f64>,
+    #[serde(default)]
+    pub start_asset_depth: Option<Amount>,
+    #[serde(default)]
+    pub start_rune_depth: Option<Amount>,
+    #[serde(default, rename = "startLPUnits")]
+    pub start_lp_units: Option<Amount>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub start_member_count: Option<i64>,
+    #[serde(default)]
+    pub start_synth_units: Option<Amount>,
+    #[serde(default)]
+    pub end_asset_depth: Option<Amount>,
+    #[serde(default)]
+    pub end_rune_depth: Option<Amount>,
+    #[serde(default, rename = "endLPUnits")]
+    pub end_lp_units: Option<Amount>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub end_member_count: Option<i64>,
+    #[serde(default)]
+    pub end_synth_units: Option<Amount>,
 }
 
 #[serde_as]
-#[derive(Debug , Serialize , Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunePoolInterval {
+    // Not part of the Midgard response, filled in from the requested interval
+    #[serde(default)]
+    pub granularity: Interval,
+
     #[serde_as(as = "DisplayFromStr")]
     pub count: i64,
     
@@ -46,178 +104,742 @@ pub struct RunePoolInterval {
     #[serde_as(as = "DisplayFromStr")]
     pub start_time: i64,
     
+    pub units: Amount,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+// `meta` block of `/history/runepool`: totals at the start and end of the requested window
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunePoolMeta {
+    #[serde_as(as = "DisplayFromStr")]
+    pub end_count: i64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub end_time: i64,
+
+    pub end_units: Amount,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub start_count: i64,
+
     #[serde_as(as = "DisplayFromStr")]
-    pub units: i64,
+    pub start_time: i64,
+
+    pub start_units: Amount,
 }
 
 #[serde_as]
-#[derive(Debug , Serialize , Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Pool {
-    #[serde_as(as = "DisplayFromStr")]
-    pub asset_liquidity_fees: i64,
+    pub asset_liquidity_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub earnings: i64,
+    pub earnings: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub pool: String,
+    pub pool: AnyAsset,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub rewards: i64,
+    pub rewards: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub rune_liquidity_fees: i64,
+    pub rune_liquidity_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub saver_earning: i64,
+    pub saver_earning: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub total_liquidity_fees_rune: i64,
+    pub total_liquidity_fees_rune: Amount,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
 }
 
 #[serde_as]
-#[derive(Debug , Serialize , Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EarningInterval {
-    #[serde_as(as = "DisplayFromStr")]
+    // Not part of the Midgard response, filled in from the requested interval
+    #[serde(default)]
+    pub granularity: Interval,
+
+    #[serde_as(as = "Finite")]
     pub avg_node_count: f64,
 
-    #[serde_as(as = "DisplayFromStr")]
-    pub block_rewards: f64,
+    pub block_rewards: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub bonding_earnings: f64,
+    pub bonding_earnings: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub earnings: f64,
+    pub earnings: Amount,
     
     #[serde_as(as = "DisplayFromStr")]
     pub end_time: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub liquidity_earnings: f64,
+    pub liquidity_earnings: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub liquidity_fees: f64,
+    pub liquidity_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     #[serde(rename = "runePriceUSD")]
     pub rune_price_usd: f64,
     
     #[serde_as(as = "DisplayFromStr")]
     
     pub start_time: i64,
-    pub pools: Vec<Pool> 
+    pub pools: Vec<Pool>,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+impl EarningInterval {
+    // One row per entry of `pools`, written alongside the interval
+    pub fn pool_earnings(&self) -> Vec<PoolEarnings> {
+        self.pools
+            .iter()
+            .map(|pool| PoolEarnings {
+                pool: pool.pool.clone(),
+                granularity: self.granularity,
+                start_time: self.start_time,
+                end_time: self.end_time,
+                asset_liquidity_fees: pool.asset_liquidity_fees,
+                earnings: pool.earnings,
+                rewards: pool.rewards,
+                rune_liquidity_fees: pool.rune_liquidity_fees,
+                saver_earning: pool.saver_earning,
+                total_liquidity_fees_rune: pool.total_liquidity_fees_rune,
+            })
+            .collect()
+    }
+}
+
+// A pool's earnings over one interval, stored on its own and keyed by pool, start and end
+// time, so a pool's earnings can be read without unpacking every `EarningInterval`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolEarnings {
+    pub pool: AnyAsset,
+    pub granularity: Interval,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub asset_liquidity_fees: Amount,
+    pub earnings: Amount,
+    pub rewards: Amount,
+    pub rune_liquidity_fees: Amount,
+    pub saver_earning: Amount,
+    pub total_liquidity_fees_rune: Amount,
 }
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwapsInterval {
-    #[serde_as(as = "DisplayFromStr")]
+    // Not part of the Midgard response, filled in from the requested pool filter
+    #[serde(default)]
+    pub pool: PoolScope,
+    // Not part of the Midgard response, filled in from the requested interval
+    #[serde(default)]
+    pub granularity: Interval,
+
+    #[serde_as(as = "Finite")]
     pub average_slip: f64,
     
     #[serde_as(as = "DisplayFromStr")]
     pub end_time: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub from_trade_average_slip: f64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub from_trade_count: f64,
+    #[serde_as(as = "Count")]
+    pub from_trade_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub from_trade_fees: f64,
+    pub from_trade_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub from_trade_volume: f64,
+    pub from_trade_volume: Amount,
     
     #[serde(rename = "fromTradeVolumeUSD")]
-    #[serde_as(as = "DisplayFromStr")]
-    pub from_trade_volume_usd: f64,
+    pub from_trade_volume_usd: Amount,
     
     #[serde(rename = "runePriceUSD")]
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub rune_price_usd: f64,
     
     #[serde_as(as = "DisplayFromStr")]
     pub start_time: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub synth_mint_average_slip: f64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_mint_count: f64,
+    #[serde_as(as = "Count")]
+    pub synth_mint_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_mint_fees: f64,
+    pub synth_mint_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_mint_volume: f64,
+    pub synth_mint_volume: Amount,
     
     #[serde(rename = "synthMintVolumeUSD")]
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_mint_volume_usd: f64,
+    pub synth_mint_volume_usd: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub synth_redeem_average_slip: f64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_redeem_count: f64,
+    #[serde_as(as = "Count")]
+    pub synth_redeem_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_redeem_fees: f64,
+    pub synth_redeem_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_redeem_volume: f64,
+    pub synth_redeem_volume: Amount,
     
     #[serde(rename = "synthRedeemVolumeUSD")]
-    #[serde_as(as = "DisplayFromStr")]
-    pub synth_redeem_volume_usd: f64,
+    pub synth_redeem_volume_usd: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub to_asset_average_slip: f64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_asset_count: f64,
+    #[serde_as(as = "Count")]
+    pub to_asset_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_asset_fees: f64,
+    pub to_asset_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_asset_volume: f64,
+    pub to_asset_volume: Amount,
     
     #[serde(rename = "toAssetVolumeUSD")]
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_asset_volume_usd: f64,
+    pub to_asset_volume_usd: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Finite")]
     pub to_rune_average_slip: f64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_rune_count: f64,
+    #[serde_as(as = "Count")]
+    pub to_rune_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_rune_fees: f64,
+    pub to_rune_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_rune_volume: f64,
+    pub to_rune_volume: Amount,
     
     #[serde(rename = "toRuneVolumeUSD")]
-    #[serde_as(as = "DisplayFromStr")]
-    pub to_rune_volume_usd: f64,
+    pub to_rune_volume_usd: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub total_count: f64,
+    #[serde_as(as = "Count")]
+    pub total_count: i64,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub total_fees: f64,
+    pub total_fees: Amount,
     
-    #[serde_as(as = "DisplayFromStr")]
-    pub total_volume: f64,
+    pub total_volume: Amount,
 
     #[serde(rename = "totalVolumeUSD")]
+    pub total_volume_usd: Amount,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolDepth {
+    pub pool: AnyAsset,
+
+    pub total_depth: Amount,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TvlInterval {
+    // Not part of the Midgard response, filled in from the requested interval
+    #[serde(default)]
+    pub granularity: Interval,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub end_time: i64,
+
+    pub pools_depth: Vec<PoolDepth>,
+
+    #[serde_as(as = "Finite")]
+    #[serde(rename = "runePriceUSD")]
+    pub rune_price_usd: f64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub start_time: i64,
+
+    pub total_value_bonded: Amount,
+
+    pub total_value_locked: Amount,
+
+    pub total_value_pooled: Amount,
+    // Fields Midgard sent that the struct does not know, kept when capture is enabled (see `SchemaDrift`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+// Current state of a pool as returned by `/pools` and `/pool/{asset}`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolDetail {
+    // Not part of the Midgard response, set when the snapshot is taken
+    #[serde(default)]
+    pub snapshot_time: i64,
+
+    pub asset: Asset,
+
+    pub status: String,
+
+    pub asset_depth: Amount,
+
+    pub rune_depth: Amount,
+
+    #[serde_as(as = "Finite")]
+    pub asset_price: f64,
+
+    #[serde_as(as = "Finite")]
+    #[serde(rename = "assetPriceUSD")]
+    pub asset_price_usd: f64,
+
+    #[serde_as(as = "Finite")]
+    pub annual_percentage_rate: f64,
+
+    #[serde_as(as = "Finite")]
+    #[serde(rename = "poolAPY")]
+    pub pool_apy: f64,
+
+    pub earnings: Amount,
+
+    #[serde_as(as = "Finite")]
+    pub earnings_annual_as_percent_of_depth: f64,
+
+    pub liquidity_units: Amount,
+
+    pub synth_units: Amount,
+
+    pub synth_supply: Amount,
+
+    pub units: Amount,
+
+    pub volume24h: Amount,
+
+    pub savers_depth: Amount,
+
+    pub savers_units: Amount,
+
+    #[serde_as(as = "Finite")]
+    #[serde(rename = "saversAPR")]
+    pub savers_apr: f64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub native_decimal: i64,
+}
+
+// Liquidity provider position of a member in one pool, from `/member/{address}`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberPool {
+    // Not part of the Midgard response: the watched address and when the snapshot was taken
+    #[serde(default)]
+    pub member_address: String,
+    #[serde(default)]
+    pub snapshot_time: i64,
+
+    pub pool: Asset,
+
+    #[serde(default)]
+    pub rune_address: String,
+
+    #[serde(default)]
+    pub asset_address: String,
+
+    pub liquidity_units: Amount,
+
+    pub rune_added: Amount,
+
+    pub asset_added: Amount,
+
+    pub rune_withdrawn: Amount,
+
+    pub asset_withdrawn: Amount,
+
+    pub rune_pending: Amount,
+
+    pub asset_pending: Amount,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub date_first_added: i64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub date_last_added: i64,
+}
+
+// RUNEPool position of a provider, from `/runepool/{address}`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunePoolProvider {
+    // Not part of the Midgard response: the watched address and when the snapshot was taken
+    #[serde(default)]
+    pub member_address: String,
+    #[serde(default)]
+    pub snapshot_time: i64,
+
+    pub rune_address: String,
+
+    pub units: Amount,
+
+    pub rune_added: Amount,
+
+    pub rune_deposit: Amount,
+
+    pub rune_withdrawn: Amount,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub date_first_added: i64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub date_last_added: i64,
+}
+
+// Savers position of a member in one pool, from `/saver/{address}`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaverPosition {
+    // Not part of the Midgard response: the watched address and when the snapshot was taken
+    #[serde(default)]
+    pub member_address: String,
+    #[serde(default)]
+    pub snapshot_time: i64,
+
+    pub pool: Asset,
+
+    pub asset_address: String,
+
+    pub saver_units: Amount,
+
+    pub asset_added: Amount,
+
+    pub asset_deposit: Amount,
+
+    pub asset_redeem: Amount,
+
+    pub asset_withdrawn: Amount,
+
     #[serde_as(as = "DisplayFromStr")]
-    pub total_volume_usd: f64,
-}
\ No newline at end of file
+    pub date_first_added: i64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub date_last_added: i64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coin {
+    pub asset: AnyAsset,
+
+    pub amount: Amount,
+}
+
+// Inbound or outbound transaction of an action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionTransaction {
+    #[serde(default)]
+    pub address: String,
+
+    #[serde(default)]
+    pub coins: Vec<Coin>,
+
+    #[serde(rename = "txID", default)]
+    pub tx_id: String,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapMetadata {
+    pub affiliate_address: Option<String>,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub affiliate_fee: Option<i64>,
+
+    pub is_streaming_swap: Option<bool>,
+
+    pub liquidity_fee: Option<Amount>,
+
+    pub memo: Option<String>,
+
+    #[serde(default)]
+    pub network_fees: Vec<Coin>,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub swap_slip: Option<i64>,
+
+    pub swap_target: Option<Amount>,
+
+    pub tx_type: Option<String>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddLiquidityMetadata {
+    pub liquidity_units: Option<Amount>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawMetadata {
+    #[serde_as(as = "Option<Finite>")]
+    pub asymmetry: Option<f64>,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub basis_points: Option<i64>,
+
+    pub impermanent_loss_protection: Option<Amount>,
+
+    pub liquidity_units: Option<Amount>,
+
+    pub memo: Option<String>,
+
+    #[serde(default)]
+    pub network_fees: Vec<Coin>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundMetadata {
+    pub affiliate_address: Option<String>,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub affiliate_fee: Option<i64>,
+
+    pub memo: Option<String>,
+
+    #[serde(default)]
+    pub network_fees: Vec<Coin>,
+
+    pub reason: Option<String>,
+
+    pub tx_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendMetadata {
+    pub code: Option<String>,
+
+    pub memo: Option<String>,
+
+    #[serde(default)]
+    pub network_fees: Vec<Coin>,
+
+    pub reason: Option<String>,
+}
+
+// Only the entry matching the action's `type` is present
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionMetadata {
+    pub swap: Option<SwapMetadata>,
+    pub add_liquidity: Option<AddLiquidityMetadata>,
+    pub withdraw: Option<WithdrawMetadata>,
+    pub refund: Option<RefundMetadata>,
+    pub send: Option<SendMetadata>,
+}
+
+// Individual action from `/actions`
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Action {
+    // Not part of the Midgard response, see `Action::key`
+    #[serde(default)]
+    pub tx_id: String,
+
+    // Nanoseconds since the epoch
+    #[serde_as(as = "DisplayFromStr")]
+    pub date: i64,
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub height: i64,
+
+    #[serde(rename = "in")]
+    pub in_txs: Vec<ActionTransaction>,
+
+    #[serde(rename = "out")]
+    pub out_txs: Vec<ActionTransaction>,
+
+    pub pools: Vec<AnyAsset>,
+
+    pub status: String,
+
+    #[serde(rename = "type")]
+    pub action_type: String,
+
+    #[serde(default)]
+    pub metadata: ActionMetadata,
+}
+
+impl Action {
+    // Identifier actions are stored under: the inbound tx id, falling back to the outbound
+    // one and finally to height/date for actions that carry no tx id at all. The type is part
+    // of it, as one inbound tx can produce several actions, e.g. a partly filled streaming
+    // swap and its refund.
+    pub fn key(&self) -> String {
+        let id = self.in_txs
+            .iter()
+            .chain(self.out_txs.iter())
+            .map(|tx| tx.tx_id.as_str())
+            .find(|tx_id| !tx_id.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}-{}", self.height, self.date));
+        format!("{}-{}", id, self.action_type)
+    }
+}
+
+// Common accessors for the `/history/*` interval types, used when paging through a time range.
+// Also implemented by the `meta` blocks, which describe the whole window of a response.
+pub trait HistoryInterval {
+    fn start_time(&self) -> i64;
+    fn end_time(&self) -> i64;
+
+    // Tags a fetched interval with the granularity it was requested at
+    fn set_granularity(&mut self, _granularity: Interval) {}
+}
+
+impl HistoryInterval for DepthInterval {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+    fn set_granularity(&mut self, granularity: Interval) { self.granularity = granularity }
+}
+
+impl HistoryInterval for SwapsInterval {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+    fn set_granularity(&mut self, granularity: Interval) { self.granularity = granularity }
+}
+
+impl HistoryInterval for EarningInterval {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+    fn set_granularity(&mut self, granularity: Interval) { self.granularity = granularity }
+}
+
+impl HistoryInterval for RunePoolInterval {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+    fn set_granularity(&mut self, granularity: Interval) { self.granularity = granularity }
+}
+
+impl HistoryInterval for TvlInterval {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+    fn set_granularity(&mut self, granularity: Interval) { self.granularity = granularity }
+}
+
+impl HistoryInterval for DepthMeta {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+}
+
+impl HistoryInterval for RunePoolMeta {
+    fn start_time(&self) -> i64 { self.start_time }
+    fn end_time(&self) -> i64 { self.end_time }
+}
+
+// The `meta` blocks of these endpoints aggregate the window into a single interval of the same shape
+pub type SwapsMeta = SwapsInterval;
+pub type EarningsMeta = EarningInterval;
+pub type TvlMeta = TvlInterval;
+
+// One page of a `/history/*` response
+#[derive(Debug)]
+pub struct HistoryResponse<T, M> {
+    pub meta: M,
+    pub intervals: Vec<T>,
+    // Intervals of the page that could not be parsed
+    pub rejected: Vec<DeadLetter>,
+}
+
+// Record Midgard sent that could not be parsed, kept with its raw JSON so it can be inspected and replayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    // Request path the record came from
+    pub source: String,
+    pub error: String,
+    pub raw: serde_json::Value,
+    pub rejected_at: i64,
+}
+
+// Stored `meta` block of one fetched page, keyed by series and the window it covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryMetaRecord {
+    pub series: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub fetched_at: i64,
+    pub meta: serde_json::Value,
+}
+
+impl HistoryMetaRecord {
+    pub fn new<M: Serialize + HistoryInterval>(series: &str, meta: &M, fetched_at: i64) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            series: series.to_string(),
+            start_time: meta.start_time(),
+            end_time: meta.end_time(),
+            fetched_at,
+            meta: serde_json::to_value(meta)?,
+        })
+    }
+}
+
+// Last stored `end_time` of a series, used to resume incremental syncs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub series: String,
+    pub end_time: i64,
+    // Cursor of the next page to fetch, for series paged by token rather than by time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Action;
+
+    fn action(in_tx: &str, out_tx: &str, action_type: &str) -> Action {
+        serde_json::from_value(json!({
+            "date": "1700000000000000000",
+            "height": "123",
+            "in": [{ "txID": in_tx }],
+            "out": [{ "txID": out_tx }],
+            "pools": [],
+            "status": "success",
+            "type": action_type,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn key_prefers_inbound_tx_id() {
+        assert_eq!(action("IN", "OUT", "swap").key(), "IN-swap");
+    }
+
+    #[test]
+    fn key_falls_back_to_outbound_tx_id() {
+        assert_eq!(action("", "OUT", "swap").key(), "OUT-swap");
+    }
+
+    #[test]
+    fn key_falls_back_to_height_and_date() {
+        assert_eq!(action("", "", "swap").key(), "123-1700000000000000000-swap");
+    }
+
+    #[test]
+    fn actions_of_the_same_tx_get_their_own_keys() {
+        let swap = action("IN", "OUT", "swap");
+        let refund = action("IN", "OUT", "refund");
+        assert_ne!(swap.key(), refund.key());
+    }
+}
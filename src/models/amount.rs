@@ -0,0 +1,215 @@
+use std::{fmt, str::FromStr};
+
+use mongodb::bson::Bson;
+use rust_decimal::Decimal;
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_content::{Data, Value};
+
+// A 1e8-denominated Midgard amount (depths, units, volumes, fees) kept as an exact decimal.
+// Midgard sends them as integer strings that do not always fit an i64 and lose precision as f64.
+// Every serializer gets the plain string back; stores with a decimal type of their own swap it
+// in with `with_native_amounts`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(pub Decimal);
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = rust_decimal::Error;
+
+    // Fails rather than rounds when `s` has more digits than fit; Decimal128 prints
+    // some values in scientific notation, e.g. `1.5E+10` or `0E-8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str_exact(s).or_else(|_| Decimal::from_scientific(s)).map(Amount)
+    }
+}
+
+// Newtype name amounts serialize under; serializers other than `serde_content` look through it
+const TOKEN: &str = "Amount";
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(TOKEN, &self.0.to_string())
+    }
+}
+
+// Serializes `record` with each amount replaced by what `native` maps it to, e.g. a BSON
+// Decimal128. The result serializes like `record` otherwise.
+pub fn with_native_amounts<T, N, E>(record: &T, native: impl Fn(Decimal) -> Result<N, E> + Copy) -> Result<Value<'static>, serde_content::Error>
+where
+    T: Serialize + ?Sized,
+    N: Serialize,
+    E: fmt::Display,
+{
+    let value = serde_content::Serializer::new().serialize(record)?.into_owned();
+    native_amounts(value, native)
+}
+
+fn native_amounts<N, E>(value: Value<'static>, native: impl Fn(Decimal) -> Result<N, E> + Copy) -> Result<Value<'static>, serde_content::Error>
+where
+    N: Serialize,
+    E: fmt::Display,
+{
+    let each = |values: Vec<Value<'static>>| values.into_iter().map(|value| native_amounts(value, native)).collect::<Result<Vec<_>, _>>();
+    let data = |data: Data<'static>| -> Result<Data<'static>, serde_content::Error> {
+        Ok(match data {
+            Data::Unit => Data::Unit,
+            Data::NewType { value } => Data::NewType { value: native_amounts(value, native)? },
+            Data::Tuple { values } => Data::Tuple { values: each(values)? },
+            Data::Struct { fields } => Data::Struct {
+                fields: fields.into_iter().map(|(name, value)| Ok((name, native_amounts(value, native)?))).collect::<Result<_, serde_content::Error>>()?,
+            },
+        })
+    };
+    Ok(match value {
+        Value::Struct(record) if record.name == TOKEN => match record.data {
+            Data::NewType { value: Value::String(amount) } => {
+                let amount: Amount = amount.parse().map_err(ser::Error::custom)?;
+                let native = native(amount.0).map_err(ser::Error::custom)?;
+                serde_content::Serializer::new().serialize(native)?.into_owned()
+            }
+            _ => return Err(ser::Error::custom("malformed amount")),
+        },
+        Value::Struct(mut record) => {
+            record.data = data(record.data)?;
+            Value::Struct(record)
+        }
+        Value::Enum(mut record) => {
+            record.data = data(record.data)?;
+            Value::Enum(record)
+        }
+        Value::Seq(values) => Value::Seq(each(values)?),
+        Value::Tuple(values) => Value::Tuple(each(values)?),
+        Value::Map(entries) => Value::Map(
+            entries.into_iter().map(|(key, value)| Ok((key, native_amounts(value, native)?))).collect::<Result<_, serde_content::Error>>()?,
+        ),
+        Value::Option(Some(value)) => Value::Option(Some(Box::new(native_amounts(*value, native)?))),
+        other => other,
+    })
+}
+
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an amount as a decimal string, an integer or a Decimal128")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Amount, E> {
+        v.parse().map_err(|e| E::custom(format!("invalid amount {:?}: {}", v, e)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Amount, E> {
+        Ok(Amount(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Amount, E> {
+        Ok(Amount(v.into()))
+    }
+
+    // Rows written while amounts were floats; whatever they lost is already gone
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Amount, E> {
+        Decimal::try_from(v).map(Amount).map_err(E::custom)
+    }
+
+    // BSON hands Decimal128 values over as a `$numberDecimalBytes` map
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Amount, A::Error> {
+        match Bson::deserialize(MapAccessDeserializer::new(map))? {
+            Bson::Decimal128(decimal) => self.visit_str(&decimal.to_string()),
+            other => Err(de::Error::custom(format!("expected a Decimal128 amount, got {}", other))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, str::FromStr};
+
+    use mongodb::bson::{self, Bson, Decimal128};
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Serialize};
+
+    use super::{with_native_amounts, Amount};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Depths {
+        depth: Amount,
+        pools: Vec<Amount>,
+        pending: Option<Amount>,
+        count: i64,
+    }
+
+    fn amount(s: &str) -> Amount {
+        Amount(Decimal::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn parses_strings_numbers_and_exponents() {
+        for (raw, expected) in [
+            ("\"123456789012345678901234\"", "123456789012345678901234"),
+            ("\"-0.5\"", "-0.5"),
+            ("42", "42"),
+            ("18446744073709551615", "18446744073709551615"),
+            ("1.5", "1.5"),
+            ("\"1.5E+10\"", "15000000000"),
+            ("\"0E-8\"", "0"),
+        ] {
+            assert_eq!(serde_json::from_str::<Amount>(raw).unwrap(), amount(expected), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_non_numbers_and_overlong_amounts() {
+        for raw in ["\"abc\"", "\"\"", "\"1234567890123456789012345678901234567890\""] {
+            assert!(serde_json::from_str::<Amount>(raw).is_err(), "{} was accepted", raw);
+        }
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        assert_eq!(serde_json::to_string(&amount("123456789012345678901234")).unwrap(), "\"123456789012345678901234\"");
+    }
+
+    #[test]
+    fn maps_amounts_to_decimal128() {
+        let depths = Depths { depth: amount("123456789012345678901234"), pools: vec![amount("1.5")], pending: Some(amount("7")), count: 3 };
+        let native = with_native_amounts(&depths, |amount| Decimal128::from_str(&amount.to_string())).unwrap();
+        let document = bson::to_document(&native).unwrap();
+
+        assert_eq!(document.get("depth"), Some(&Bson::Decimal128(Decimal128::from_str("123456789012345678901234").unwrap())));
+        assert_eq!(document.get_array("pools").unwrap()[0], Bson::Decimal128(Decimal128::from_str("1.5").unwrap()));
+        assert_eq!(document.get("pending"), Some(&Bson::Decimal128(Decimal128::from_str("7").unwrap())));
+        assert_eq!(document.get("count"), Some(&Bson::Int64(3)));
+        assert_eq!(bson::from_document::<Depths>(document).unwrap(), depths);
+    }
+
+    #[test]
+    fn maps_amounts_to_surreal_decimals() {
+        use surrealdb::sql::{Number, Value};
+
+        let depths = Depths { depth: amount("123456789012345678901234"), pools: vec![amount("1.5")], pending: None, count: 3 };
+        let native = with_native_amounts(&depths, |amount| Ok::<_, Infallible>(Value::from(amount))).unwrap();
+        let Value::Object(object) = surrealdb::sql::to_value(native).unwrap() else {
+            panic!("expected an object");
+        };
+
+        assert_eq!(object.get("depth"), Some(&Value::Number(Number::Decimal(depths.depth.0))));
+        assert_eq!(object.get("pools"), Some(&Value::from(vec![Value::Number(Number::Decimal(depths.pools[0].0))])));
+        assert_eq!(object.get("count"), Some(&Value::Number(Number::Int(3))));
+    }
+}
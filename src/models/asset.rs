@@ -0,0 +1,280 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+// How an asset is held, told apart by the separator between chain and symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetKind {
+    // On its own chain, e.g. `BTC.BTC`
+    Native,
+    // Synthetic asset held on THORChain, e.g. `BTC/BTC`
+    Synth,
+    // Trade account balance, e.g. `BTC~BTC`
+    Trade,
+    // Secured asset, e.g. `BTC-BTC`
+    Secured,
+}
+
+impl AssetKind {
+    pub fn separator(self) -> char {
+        match self {
+            AssetKind::Native => '.',
+            AssetKind::Synth => '/',
+            AssetKind::Trade => '~',
+            AssetKind::Secured => '-',
+        }
+    }
+
+    fn from_separator(separator: char) -> Option<Self> {
+        match separator {
+            '.' => Some(AssetKind::Native),
+            '/' => Some(AssetKind::Synth),
+            '~' => Some(AssetKind::Trade),
+            '-' => Some(AssetKind::Secured),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid asset {asset:?}: {reason}")]
+pub struct ParseAssetError {
+    pub asset: String,
+    pub reason: &'static str,
+}
+
+// A Midgard asset such as `ETH.USDC-0XA0B86991C6218B36C1D19D4A2E9EB0CE3606EB48`: chain,
+// separator for its kind, then the symbol, which is the ticker plus the contract address
+// for tokens. Parsing uppercases, matching the notation Midgard returns.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Asset {
+    chain: String,
+    symbol: String,
+    kind: AssetKind,
+}
+
+// Parsed parts for callers that group or filter by chain and ticker
+#[allow(dead_code)]
+impl Asset {
+    pub fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn kind(&self) -> AssetKind {
+        self.kind
+    }
+
+    // Symbol without the contract address, e.g. `USDC`
+    pub fn ticker(&self) -> &str {
+        self.symbol.split('-').next().unwrap_or(&self.symbol)
+    }
+}
+
+// RUNE, which every pool is paired with
+impl Default for Asset {
+    fn default() -> Self {
+        Self { chain: "THOR".to_string(), symbol: "RUNE".to_string(), kind: AssetKind::Native }
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.chain, self.kind.separator(), self.symbol)
+    }
+}
+
+impl FromStr for Asset {
+    type Err = ParseAssetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| ParseAssetError { asset: s.to_string(), reason };
+        let asset = s.trim().to_ascii_uppercase();
+
+        // Chains are alphanumeric, so the first other character is the separator
+        let split = asset.find(|c: char| !c.is_ascii_alphanumeric()).ok_or_else(|| invalid("missing separator"))?;
+        let (chain, rest) = asset.split_at(split);
+        let mut rest = rest.chars();
+        let separator = rest.next().ok_or_else(|| invalid("missing separator"))?;
+        let kind = AssetKind::from_separator(separator).ok_or_else(|| invalid("unknown separator"))?;
+        let symbol = rest.as_str();
+
+        if chain.is_empty() {
+            return Err(invalid("missing chain"));
+        }
+        if symbol.is_empty() || symbol.starts_with('-') {
+            return Err(invalid("missing ticker"));
+        }
+        if !symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(invalid("unexpected character in symbol"));
+        }
+
+        Ok(Self { chain: chain.to_string(), symbol: symbol.to_string(), kind })
+    }
+}
+
+impl Serialize for Asset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Asset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let asset = String::deserialize(deserializer)?;
+        asset.parse().map_err(de::Error::custom)
+    }
+}
+
+// An asset inside a Midgard response, kept as sent when it does not parse so one unexpected
+// entry does not reject the whole interval or page it arrived in
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AnyAsset {
+    Known(Asset),
+    Other(String),
+}
+
+impl From<Asset> for AnyAsset {
+    fn from(asset: Asset) -> Self {
+        AnyAsset::Known(asset)
+    }
+}
+
+impl fmt::Display for AnyAsset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyAsset::Known(asset) => asset.fmt(f),
+            AnyAsset::Other(raw) => f.write_str(raw),
+        }
+    }
+}
+
+impl FromStr for AnyAsset {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse().map(AnyAsset::Known).unwrap_or_else(|_| AnyAsset::Other(s.to_string())))
+    }
+}
+
+impl Serialize for AnyAsset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyAsset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let asset = String::deserialize(deserializer)?;
+        Ok(asset.parse().map(AnyAsset::Known).unwrap_or(AnyAsset::Other(asset)))
+    }
+}
+
+// Tag of history rows that cover the whole network rather than a single pool
+pub const ALL_POOLS: &str = "all";
+
+// Pools a history row covers: the one it was fetched for, or all of them, stored as `all`
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PoolScope {
+    #[default]
+    All,
+    Pool(Asset),
+}
+
+impl From<Option<Asset>> for PoolScope {
+    fn from(pool: Option<Asset>) -> Self {
+        pool.map_or(PoolScope::All, PoolScope::Pool)
+    }
+}
+
+impl fmt::Display for PoolScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolScope::All => f.write_str(ALL_POOLS),
+            PoolScope::Pool(asset) => asset.fmt(f),
+        }
+    }
+}
+
+impl FromStr for PoolScope {
+    type Err = ParseAssetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            ALL_POOLS => Ok(PoolScope::All),
+            pool => pool.parse().map(PoolScope::Pool),
+        }
+    }
+}
+
+impl Serialize for PoolScope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PoolScope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pool = String::deserialize(deserializer)?;
+        pool.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyAsset, Asset, AssetKind, PoolScope};
+
+    #[test]
+    fn parses_each_kind() {
+        for (raw, kind) in [("BTC.BTC", AssetKind::Native), ("BTC/BTC", AssetKind::Synth), ("BTC~BTC", AssetKind::Trade), ("BTC-BTC", AssetKind::Secured)] {
+            let asset: Asset = raw.parse().unwrap();
+            assert_eq!(asset.kind(), kind);
+            assert_eq!(asset.chain(), "BTC");
+            assert_eq!(asset.symbol(), "BTC");
+            assert_eq!(asset.to_string(), raw);
+        }
+    }
+
+    #[test]
+    fn splits_token_ticker_from_contract() {
+        let asset: Asset = "eth.usdc-0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".parse().unwrap();
+        assert_eq!(asset.to_string(), "ETH.USDC-0XA0B86991C6218B36C1D19D4A2E9EB0CE3606EB48");
+        assert_eq!(asset.ticker(), "USDC");
+    }
+
+    #[test]
+    fn rejects_malformed_assets() {
+        for (raw, reason) in [
+            ("BTC", "missing separator"),
+            ("BTC:BTC", "unknown separator"),
+            (".BTC", "missing chain"),
+            ("BTC.", "missing ticker"),
+            ("ETH.-0XA0B8", "missing ticker"),
+            ("BTC.B$C", "unexpected character in symbol"),
+        ] {
+            assert_eq!(raw.parse::<Asset>().unwrap_err().reason, reason, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn keeps_unparsed_assets_as_sent() {
+        let known: AnyAsset = serde_json::from_str("\"BTC.BTC\"").unwrap();
+        assert_eq!(known, AnyAsset::Known("BTC.BTC".parse().unwrap()));
+        let other: AnyAsset = serde_json::from_str("\"THOR.RUNE:X\"").unwrap();
+        assert_eq!(other, AnyAsset::Other("THOR.RUNE:X".to_string()));
+        assert_eq!(serde_json::to_string(&other).unwrap(), "\"THOR.RUNE:X\"");
+    }
+
+    #[test]
+    fn pool_scope_round_trips_all() {
+        assert_eq!("all".parse::<PoolScope>().unwrap(), PoolScope::All);
+        assert_eq!(PoolScope::All.to_string(), "all");
+        let pool: PoolScope = "BTC.BTC".parse().unwrap();
+        assert_eq!(pool, PoolScope::Pool("BTC.BTC".parse().unwrap()));
+        assert!("BTC".parse::<PoolScope>().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use serde::{
+    de::{self, Visitor},
+    Deserializer, Serializer,
+};
+use serde_with::{DeserializeAs, SerializeAs};
+
+// `#[serde_as(as = "Count")]` for whole-number counts Midgard sends as strings, e.g. swap counts.
+// JSON gets the string back; BSON and SurrealDB get a native integer. Anything that is not
+// a whole number is rejected rather than truncated.
+pub struct Count;
+
+impl SerializeAs<i64> for Count {
+    fn serialize_as<S: Serializer>(count: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(count)
+        } else {
+            serializer.serialize_i64(*count)
+        }
+    }
+}
+
+struct CountVisitor;
+
+impl<'de> Visitor<'de> for CountVisitor {
+    type Value = i64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a whole-number count as a string or an integer")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<i64, E> {
+        if let Ok(count) = v.parse() {
+            return Ok(count);
+        }
+        match v.parse::<f64>() {
+            Ok(count) => self.visit_f64(count),
+            Err(_) => Err(E::custom(format!("invalid count {:?}", v))),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<i64, E> {
+        Ok(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<i64, E> {
+        i64::try_from(v).map_err(E::custom)
+    }
+
+    // Rows written while counts were floats hold whole numbers
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<i64, E> {
+        if v.fract() != 0.0 || v.abs() >= i64::MAX as f64 {
+            return Err(E::custom(format!("count {} is not a whole number", v)));
+        }
+        Ok(v as i64)
+    }
+}
+
+impl<'de> DeserializeAs<'de, i64> for Count {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        deserializer.deserialize_any(CountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_with::serde_as;
+
+    use super::Count;
+
+    #[serde_as]
+    #[derive(Debug, Deserialize)]
+    struct Swaps(#[serde_as(as = "Count")] i64);
+
+    #[test]
+    fn parses_whole_numbers() {
+        assert_eq!(serde_json::from_str::<Swaps>("\"42\"").unwrap().0, 42);
+        assert_eq!(serde_json::from_str::<Swaps>("42").unwrap().0, 42);
+        assert_eq!(serde_json::from_str::<Swaps>("\"42.0\"").unwrap().0, 42);
+        assert_eq!(serde_json::from_str::<Swaps>("42.0").unwrap().0, 42);
+    }
+
+    #[test]
+    fn rejects_fractions() {
+        for raw in ["\"42.5\"", "42.5", "\"1e300\""] {
+            assert!(serde_json::from_str::<Swaps>(raw).is_err(), "{} was accepted", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert!(serde_json::from_str::<Swaps>("\"abc\"").is_err());
+        assert!(serde_json::from_str::<Swaps>("18446744073709551615").is_err());
+    }
+}
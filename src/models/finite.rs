@@ -0,0 +1,54 @@
+use serde::{de, Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+
+// `#[serde_as(as = "Finite")]` for prices, rates and slips Midgard sends as decimal strings.
+// Stored like `DisplayFromStr` would, but `NaN` and infinities are rejected so the record
+// ends up in the dead letters instead of the database.
+pub struct Finite;
+
+impl SerializeAs<f64> for Finite {
+    fn serialize_as<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+}
+
+impl<'de> DeserializeAs<'de, f64> for Finite {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        match raw.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            Ok(_) => Err(de::Error::custom(format!("{:?} is not a finite number", raw))),
+            Err(e) => Err(de::Error::custom(format!("invalid number {:?}: {}", raw, e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_with::serde_as;
+
+    use super::Finite;
+
+    #[serde_as]
+    #[derive(Debug, Deserialize)]
+    struct Price(#[serde_as(as = "Finite")] f64);
+
+    #[test]
+    fn parses_decimal_strings() {
+        assert_eq!(serde_json::from_str::<Price>("\"1.25\"").unwrap().0, 1.25);
+        assert_eq!(serde_json::from_str::<Price>("\"-0.5\"").unwrap().0, -0.5);
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        for raw in ["\"NaN\"", "\"inf\"", "\"-infinity\""] {
+            assert!(serde_json::from_str::<Price>(raw).is_err(), "{} was accepted", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert!(serde_json::from_str::<Price>("\"abc\"").is_err());
+    }
+}
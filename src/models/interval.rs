@@ -0,0 +1,229 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, TimeDelta, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+// Midgard rejects history requests with a larger `count`
+pub const MAX_INTERVAL_COUNT: i64 = 400;
+
+// Granularity of a `/history/*` series. Stored with every interval, so the same series can
+// be kept at several granularities; records stored before that were all hourly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Interval {
+    FiveMin,
+    #[default]
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Interval {
+    pub const ALL: [Interval; 7] =
+        [Interval::FiveMin, Interval::Hour, Interval::Day, Interval::Week, Interval::Month, Interval::Quarter, Interval::Year];
+
+    // Name Midgard uses in `?interval=`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Interval::FiveMin => "5min",
+            Interval::Hour => "hour",
+            Interval::Day => "day",
+            Interval::Week => "week",
+            Interval::Month => "month",
+            Interval::Quarter => "quarter",
+            Interval::Year => "year",
+        }
+    }
+
+    // Start of the bucket containing `t`. Buckets are UTC aligned and weeks start on Monday.
+    pub fn bucket_start(self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let fixed = |len: i64| t - TimeDelta::seconds(t.timestamp().rem_euclid(len));
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+        let date = t.date_naive();
+        let first_of = |month: u32| NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap_or(date);
+        match self {
+            Interval::FiveMin => fixed(300),
+            Interval::Hour => fixed(3600),
+            Interval::Day => midnight(date),
+            Interval::Week => midnight(date - Days::new(date.weekday().num_days_from_monday().into())),
+            Interval::Month => midnight(first_of(date.month())),
+            Interval::Quarter => midnight(first_of(date.month0() / 3 * 3 + 1)),
+            Interval::Year => midnight(first_of(1)),
+        }
+    }
+
+    // Start of the bucket following the one containing `t`, i.e. when it closes;
+    // `None` past the range chrono can represent
+    pub fn bucket_end(self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = self.bucket_start(t);
+        match self {
+            Interval::FiveMin => start.checked_add_signed(TimeDelta::minutes(5)),
+            Interval::Hour => start.checked_add_signed(TimeDelta::hours(1)),
+            Interval::Day => start.checked_add_days(Days::new(1)),
+            Interval::Week => start.checked_add_days(Days::new(7)),
+            Interval::Month => start.checked_add_months(Months::new(1)),
+            Interval::Quarter => start.checked_add_months(Months::new(3)),
+            Interval::Year => start.checked_add_months(Months::new(12)),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Interval::ALL
+            .into_iter()
+            .find(|interval| interval.as_str() == s)
+            .ok_or_else(|| format!("unknown interval {:?}, expected one of 5min, hour, day, week, month, quarter, year", s))
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let interval = String::deserialize(deserializer)?;
+        interval.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WindowError {
+    #[error("window starts at {0}, before the unix epoch")]
+    BeforeEpoch(i64),
+
+    #[error("window ends at {end}, before it starts at {start}")]
+    Reversed { start: i64, end: i64 },
+
+    #[error("timestamp {0} is out of range")]
+    OutOfRange(i64),
+
+    #[error("{0} intervals requested, Midgard returns between 1 and {MAX_INTERVAL_COUNT}")]
+    Count(i64),
+}
+
+// Span of unix seconds from `start_time` up to `end_time`, as Midgard's `from` and `to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    start_time: i64,
+    end_time: i64,
+}
+
+impl TimeWindow {
+    pub fn new(start_time: i64, end_time: i64) -> Result<Self, WindowError> {
+        if start_time < 0 {
+            return Err(WindowError::BeforeEpoch(start_time));
+        }
+        if end_time < start_time {
+            return Err(WindowError::Reversed { start: start_time, end: end_time });
+        }
+        if DateTime::from_timestamp(end_time, 0).is_none() {
+            return Err(WindowError::OutOfRange(end_time));
+        }
+        Ok(Self { start_time, end_time })
+    }
+
+    pub fn from_datetimes(start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Self, WindowError> {
+        Self::new(start.timestamp(), end.timestamp())
+    }
+
+    pub fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    pub fn end_time(&self) -> i64 {
+        self.end_time
+    }
+
+    // Both ends were checked to be representable in `new`
+    pub fn start(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.start_time, 0).unwrap_or_default()
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.end_time, 0).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start_time == self.end_time
+    }
+
+    // The rest of the window from `start_time`, e.g. a stored checkpoint; empty once past the end
+    pub fn starting_at(&self, start_time: i64) -> Self {
+        Self { start_time: start_time.clamp(self.start_time, self.end_time), ..*self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{Interval, TimeWindow, WindowError};
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn buckets_align_to_utc_boundaries() {
+        // A Thursday
+        let t = at(2024, 5, 16, 13, 47);
+        for (interval, start, end) in [
+            (Interval::FiveMin, at(2024, 5, 16, 13, 45), at(2024, 5, 16, 13, 50)),
+            (Interval::Hour, at(2024, 5, 16, 13, 0), at(2024, 5, 16, 14, 0)),
+            (Interval::Day, at(2024, 5, 16, 0, 0), at(2024, 5, 17, 0, 0)),
+            (Interval::Week, at(2024, 5, 13, 0, 0), at(2024, 5, 20, 0, 0)),
+            (Interval::Month, at(2024, 5, 1, 0, 0), at(2024, 6, 1, 0, 0)),
+            (Interval::Quarter, at(2024, 4, 1, 0, 0), at(2024, 7, 1, 0, 0)),
+            (Interval::Year, at(2024, 1, 1, 0, 0), at(2025, 1, 1, 0, 0)),
+        ] {
+            assert_eq!(interval.bucket_start(t), start, "{}", interval);
+            assert_eq!(interval.bucket_end(t), Some(end), "{}", interval);
+        }
+    }
+
+    #[test]
+    fn bucket_start_is_its_own_bucket() {
+        let t = at(2024, 12, 31, 23, 59);
+        for interval in Interval::ALL {
+            let start = interval.bucket_start(t);
+            assert_eq!(interval.bucket_start(start), start, "{}", interval);
+            let end = interval.bucket_end(t).unwrap();
+            assert_eq!(interval.bucket_start(end), end, "{}", interval);
+        }
+    }
+
+    #[test]
+    fn round_trips_midgard_names() {
+        for interval in Interval::ALL {
+            assert_eq!(interval.as_str().parse::<Interval>(), Ok(interval));
+        }
+        assert!("hourly".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn validates_windows() {
+        assert_eq!(TimeWindow::new(-1, 10), Err(WindowError::BeforeEpoch(-1)));
+        assert_eq!(TimeWindow::new(10, 5), Err(WindowError::Reversed { start: 10, end: 5 }));
+        assert_eq!(TimeWindow::new(0, i64::MAX), Err(WindowError::OutOfRange(i64::MAX)));
+
+        let window = TimeWindow::new(100, 200).unwrap();
+        assert_eq!(window.starting_at(150).start_time(), 150);
+        assert_eq!(window.starting_at(50).start_time(), 100);
+        assert!(window.starting_at(300).is_empty());
+    }
+}
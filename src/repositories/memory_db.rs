@@ -0,0 +1,170 @@
+use std::{error::Error, sync::Mutex, time::Duration};
+use async_trait::async_trait;
+
+use crate::{models::{Action, Asset, Checkpoint, DeadLetter, DepthInterval, EarningInterval, HistoryMetaRecord, Interval, MemberPool, PoolDetail, PoolEarnings, RunePoolInterval, RunePoolProvider, SaverPosition, SwapsInterval, TvlInterval}, services::db_traits::Database};
+
+// In-memory stand-in for the real databases in tests, with the same upsert keys
+#[derive(Default)]
+pub struct MemoryDb {
+    depth_intervals: Mutex<Vec<DepthInterval>>,
+    swaps_intervals: Mutex<Vec<SwapsInterval>>,
+    earnings_intervals: Mutex<Vec<EarningInterval>>,
+    pool_earnings: Mutex<Vec<PoolEarnings>>,
+    runepool_intervals: Mutex<Vec<RunePoolInterval>>,
+    tvl_intervals: Mutex<Vec<TvlInterval>>,
+    pool_snapshots: Mutex<Vec<PoolDetail>>,
+    actions: Mutex<Vec<Action>>,
+    member_positions: Mutex<Vec<MemberPool>>,
+    history_metas: Mutex<Vec<HistoryMetaRecord>>,
+    runepool_providers: Mutex<Vec<RunePoolProvider>>,
+    saver_positions: Mutex<Vec<SaverPosition>>,
+    dead_letters: Mutex<Vec<DeadLetter>>,
+    checkpoints: Mutex<Vec<Checkpoint>>,
+}
+
+// Replaces the record with the same key as `record`, or appends it
+fn upsert<T>(table: &Mutex<Vec<T>>, record: T, same_key: impl Fn(&T, &T) -> bool) -> Result<Duration, Box<dyn Error>> {
+    let mut table = table.lock().unwrap();
+    match table.iter_mut().find(|stored| same_key(stored, &record)) {
+        Some(stored) => *stored = record,
+        None => table.push(record),
+    }
+    Ok(Duration::ZERO)
+}
+
+fn read<T: Clone>(table: &Mutex<Vec<T>>) -> Result<(Vec<T>, Duration), Box<dyn Error>> {
+    Ok((table.lock().unwrap().clone(), Duration::ZERO))
+}
+
+#[async_trait]
+impl Database for MemoryDb {
+    async fn store_depth_intervals(&self , interval: DepthInterval) -> Result<Duration , Box<dyn Error>> {
+        interval.pool.as_ref().ok_or("depth interval is not tagged with its pool")?;
+        upsert(&self.depth_intervals, interval, |a, b| (&a.pool, a.granularity, a.end_time) == (&b.pool, b.granularity, b.end_time))
+    }
+
+    async fn store_swaps_intervals(&self , interval: SwapsInterval) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.swaps_intervals, interval, |a, b| (&a.pool, a.granularity, a.end_time) == (&b.pool, b.granularity, b.end_time))
+    }
+
+    async fn store_earnings_intervals(&self , interval: EarningInterval) -> Result<Duration , Box<dyn Error>> {
+        for pool_earnings in interval.pool_earnings() {
+            upsert(&self.pool_earnings, pool_earnings, |a, b| {
+                (&a.pool, a.granularity, a.start_time, a.end_time) == (&b.pool, b.granularity, b.start_time, b.end_time)
+            })?;
+        }
+        upsert(&self.earnings_intervals, interval, |a, b| (a.granularity, a.end_time) == (b.granularity, b.end_time))
+    }
+
+    async fn store_runepool_intervals(&self , interval: RunePoolInterval) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.runepool_intervals, interval, |a, b| (a.granularity, a.end_time) == (b.granularity, b.end_time))
+    }
+
+    async fn store_tvl_intervals(&self , interval: TvlInterval) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.tvl_intervals, interval, |a, b| (a.granularity, a.end_time) == (b.granularity, b.end_time))
+    }
+
+    async fn read_depth_intervals(&self) -> Result<(Vec<DepthInterval>, Duration), Box<dyn Error>> {
+        read(&self.depth_intervals)
+    }
+
+    async fn read_swaps_intervals(&self) -> Result<(Vec<SwapsInterval>, Duration), Box<dyn Error>> {
+        read(&self.swaps_intervals)
+    }
+
+    async fn read_earnings_intervals(&self) -> Result<(Vec<EarningInterval>, Duration), Box<dyn Error>> {
+        read(&self.earnings_intervals)
+    }
+
+    async fn read_runepool_intervals(&self) -> Result<(Vec<RunePoolInterval>, Duration), Box<dyn Error>> {
+        read(&self.runepool_intervals)
+    }
+
+    async fn read_tvl_intervals(&self) -> Result<(Vec<TvlInterval>, Duration), Box<dyn Error>> {
+        read(&self.tvl_intervals)
+    }
+
+    async fn read_pool_earnings(&self , pool: &Asset , granularity: Interval) -> Result<(Vec<PoolEarnings>, Duration), Box<dyn Error>> {
+        let pool = pool.to_string();
+        let (mut pool_earnings, duration) = read(&self.pool_earnings)?;
+        pool_earnings.retain(|earnings| earnings.pool.to_string() == pool && earnings.granularity == granularity);
+        pool_earnings.sort_by_key(|earnings| earnings.start_time);
+        Ok((pool_earnings, duration))
+    }
+
+    async fn store_pool_snapshot(&self , pool: PoolDetail) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.pool_snapshots, pool, |a, b| (&a.asset, a.snapshot_time) == (&b.asset, b.snapshot_time))
+    }
+
+    async fn read_pool_snapshots(&self) -> Result<(Vec<PoolDetail>, Duration), Box<dyn Error>> {
+        read(&self.pool_snapshots)
+    }
+
+    async fn store_action(&self , action: Action) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.actions, action, |a, b| a.key() == b.key())
+    }
+
+    async fn read_actions(&self) -> Result<(Vec<Action>, Duration), Box<dyn Error>> {
+        read(&self.actions)
+    }
+
+    async fn store_member_position(&self , position: MemberPool) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.member_positions, position, |a, b| {
+            (&a.member_address, &a.pool, a.snapshot_time) == (&b.member_address, &b.pool, b.snapshot_time)
+        })
+    }
+
+    async fn read_member_positions(&self) -> Result<(Vec<MemberPool>, Duration), Box<dyn Error>> {
+        read(&self.member_positions)
+    }
+
+    async fn store_history_meta(&self , record: HistoryMetaRecord) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.history_metas, record, |a, b| (&a.series, a.start_time, a.end_time) == (&b.series, b.start_time, b.end_time))
+    }
+
+    async fn read_history_metas(&self) -> Result<(Vec<HistoryMetaRecord>, Duration), Box<dyn Error>> {
+        read(&self.history_metas)
+    }
+
+    async fn store_runepool_provider(&self , provider: RunePoolProvider) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.runepool_providers, provider, |a, b| {
+            (&a.member_address, &a.rune_address, a.snapshot_time) == (&b.member_address, &b.rune_address, b.snapshot_time)
+        })
+    }
+
+    async fn read_runepool_providers(&self) -> Result<(Vec<RunePoolProvider>, Duration), Box<dyn Error>> {
+        read(&self.runepool_providers)
+    }
+
+    async fn store_saver_position(&self , position: SaverPosition) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.saver_positions, position, |a, b| {
+            (&a.member_address, &a.pool, a.snapshot_time) == (&b.member_address, &b.pool, b.snapshot_time)
+        })
+    }
+
+    async fn read_saver_positions(&self) -> Result<(Vec<SaverPosition>, Duration), Box<dyn Error>> {
+        read(&self.saver_positions)
+    }
+
+    async fn store_dead_letter(&self , dead_letter: DeadLetter) -> Result<Duration , Box<dyn Error>> {
+        self.dead_letters.lock().unwrap().push(dead_letter);
+        Ok(Duration::ZERO)
+    }
+
+    async fn read_dead_letters(&self) -> Result<(Vec<DeadLetter>, Duration), Box<dyn Error>> {
+        read(&self.dead_letters)
+    }
+
+    async fn store_checkpoint(&self , checkpoint: Checkpoint) -> Result<Duration , Box<dyn Error>> {
+        upsert(&self.checkpoints, checkpoint, |a, b| a.series == b.series)
+    }
+
+    async fn read_checkpoint(&self , series: &str) -> Result<(Option<Checkpoint>, Duration), Box<dyn Error>> {
+        let checkpoint = self.checkpoints.lock().unwrap().iter().find(|checkpoint| checkpoint.series == series).cloned();
+        Ok((checkpoint, Duration::ZERO))
+    }
+
+    async fn close(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
 pub mod postgres_db;
 pub mod mongo_db;
-pub mod surreal_db;
\ No newline at end of file
+pub mod surreal_db;
+#[cfg(test)]
+pub mod memory_db;
\ No newline at end of file
@@ -1,16 +1,139 @@
-use std::{error::Error, time::Instant};
+use std::{error::Error, str::FromStr, time::Instant};
 use async_trait::async_trait;
 use futures::StreamExt as _;
-use mongodb::{Client, Collection};
+use mongodb::{bson::{doc, Decimal128, Document, Regex}, options::IndexOptions, Client, Collection, IndexModel};
+use serde::Serialize;
+use serde_content::Value as Content;
 
-use crate::{models::{DepthInterval, EarningInterval, RunePoolInterval, SwapsInterval}, services::db_traits::Database};
+use crate::{models::{with_native_amounts, Action, ALL_POOLS, Asset, Checkpoint, DeadLetter, DepthInterval, EarningInterval, HistoryMetaRecord, Interval, MemberPool, PoolDetail, PoolEarnings, RunePoolInterval, RunePoolProvider, SaverPosition, SwapsInterval, TvlInterval}, services::db_traits::Database};
+
+const INTERVAL_COLLECTIONS: [&str; 5] = ["depth_intervals", "swaps_intervals", "earnings_intervals", "rune_intervals", "tvl_intervals"];
+
+// Network-wide series, one document per granularity and end time
+const NETWORK_INTERVAL_COLLECTIONS: [&str; 3] = ["earnings_intervals", "rune_intervals", "tvl_intervals"];
+
+// Swap counts, stored as strings before they were integers
+const COUNT_FIELDS: [&str; 6] = ["fromTradeCount", "synthMintCount", "synthRedeemCount", "toAssetCount", "toRuneCount", "totalCount"];
+
+// Top-level amount fields of each collection, stored as strings before they were Decimal128.
+// Amounts nested in arrays (earnings pools, TVL pool depths, action coins) still read back
+// from strings and are converted when the document is written again.
+const AMOUNT_FIELDS: &[(&str, &[&str])] = &[
+    ("depth_intervals", &["assetDepth", "liquidityUnits", "runeDepth", "synthSupply", "synthUnits", "units"]),
+    (
+        "swaps_intervals",
+        &[
+            "fromTradeFees",
+            "fromTradeVolume",
+            "fromTradeVolumeUSD",
+            "synthMintFees",
+            "synthMintVolume",
+            "synthMintVolumeUSD",
+            "synthRedeemFees",
+            "synthRedeemVolume",
+            "synthRedeemVolumeUSD",
+            "toAssetFees",
+            "toAssetVolume",
+            "toAssetVolumeUSD",
+            "toRuneFees",
+            "toRuneVolume",
+            "toRuneVolumeUSD",
+            "totalFees",
+            "totalVolume",
+            "totalVolumeUSD",
+        ],
+    ),
+    ("earnings_intervals", &["blockRewards", "bondingEarnings", "earnings", "liquidityEarnings", "liquidityFees"]),
+    ("rune_intervals", &["units"]),
+    ("tvl_intervals", &["totalValueBonded", "totalValueLocked", "totalValuePooled"]),
+    (
+        "pool_snapshots",
+        &[
+            "assetDepth",
+            "runeDepth",
+            "earnings",
+            "liquidityUnits",
+            "synthUnits",
+            "synthSupply",
+            "units",
+            "volume24h",
+            "saversDepth",
+            "saversUnits",
+        ],
+    ),
+    (
+        "member_positions",
+        &[
+            "liquidityUnits",
+            "runeAdded",
+            "assetAdded",
+            "runeWithdrawn",
+            "assetWithdrawn",
+            "runePending",
+            "assetPending",
+        ],
+    ),
+    ("runepool_providers", &["units", "runeAdded", "runeDeposit", "runeWithdrawn"]),
+    ("saver_positions", &["saverUnits", "assetAdded", "assetDeposit", "assetRedeem", "assetWithdrawn"]),
+];
+
+// Serializes a record for writing, with its amounts as Decimal128
+fn with_decimals<T: Serialize>(record: &T) -> Result<Content<'static>, serde_content::Error> {
+    with_native_amounts(record, |amount| Decimal128::from_str(&amount.to_string()))
+}
+
+// Handle on `collection` for writing records that went through `with_decimals`
+fn writes<T: Send + Sync>(collection: &Collection<T>) -> Collection<Content<'static>> {
+    collection.clone_with_type()
+}
+
+// Builds a unique index on `fields`. Documents used to be inserted again on every fetch, so
+// only the newest copy of each key is kept first, or the index build would fail.
+async fn unique_index<T: Send + Sync>(collection: &Collection<T>, fields: &[&str]) -> Result<(), Box<dyn Error>> {
+    let collection = collection.clone_with_type::<Document>();
+    let key: Document = fields.iter().map(|field| (field.to_string(), format!("${}", field).into())).collect();
+    let mut duplicates = collection
+        .aggregate(vec![
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$group": { "_id": key, "ids": { "$push": "$_id" } } },
+            doc! { "$match": { "ids.1": { "$exists": true } } },
+        ])
+        .await?;
+    while let Some(group) = duplicates.next().await {
+        let mut ids = group?.get_array("ids")?.clone();
+        ids.pop();
+        collection.delete_many(doc! { "_id": { "$in": ids } }).await?;
+    }
+    let keys: Document = fields.iter().map(|field| (field.to_string(), 1.into())).collect();
+    collection
+        .create_index(
+            IndexModel::builder()
+                .keys(keys)
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+    Ok(())
+}
 
 pub struct MongoDb {
     client: Client,
     depth_collection: Collection<DepthInterval>,
     swaps_collection: Collection<SwapsInterval>,
     earnings_collection: Collection<EarningInterval>,
+    pool_earnings_collection: Collection<PoolEarnings>,
     rune_collection: Collection<RunePoolInterval>,
+    tvl_collection: Collection<TvlInterval>,
+    pool_snapshot_collection: Collection<PoolDetail>,
+    action_collection: Collection<Action>,
+    member_collection: Collection<MemberPool>,
+    history_meta_collection: Collection<HistoryMetaRecord>,
+    runepool_provider_collection: Collection<RunePoolProvider>,
+    saver_collection: Collection<SaverPosition>,
+    checkpoint_collection: Collection<Checkpoint>,
+    dead_letter_collection: Collection<DeadLetter>,
+    // Multi-document transactions need a replica set or sharded cluster
+    transactions: bool,
 }
 
 impl MongoDb {
@@ -21,14 +144,146 @@ impl MongoDb {
         let depth_collection = db.collection::<DepthInterval>("depth_intervals");
         let swaps_collection = db.collection::<SwapsInterval>("swaps_intervals");
         let earnings_collection = db.collection::<EarningInterval>("earnings_intervals");
+        let pool_earnings_collection = db.collection::<PoolEarnings>("pool_earnings");
         let rune_collection = db.collection::<RunePoolInterval>("rune_intervals");
+        let tvl_collection = db.collection::<TvlInterval>("tvl_intervals");
+        let pool_snapshot_collection = db.collection::<PoolDetail>("pool_snapshots");
+        let action_collection = db.collection::<Action>("actions");
+        let member_collection = db.collection::<MemberPool>("member_positions");
+        let history_meta_collection = db.collection::<HistoryMetaRecord>("history_meta");
+        let runepool_provider_collection = db.collection::<RunePoolProvider>("runepool_providers");
+        let saver_collection = db.collection::<SaverPosition>("saver_positions");
+        let checkpoint_collection = db.collection::<Checkpoint>("checkpoints");
+        let dead_letter_collection = db.collection::<DeadLetter>("dead_letters");
+
+        // Interval documents written before they were tagged with their granularity were all hourly
+        for collection in INTERVAL_COLLECTIONS {
+            db.collection::<Document>(collection)
+                .update_many(doc! { "granularity": { "$exists": false } }, doc! { "$set": { "granularity": "hour" } })
+                .await?;
+            // Hourly and daily intervals can end at the same time
+            let indexes = db.collection::<Document>(collection).list_index_names().await?;
+            if indexes.iter().any(|index| index == "pool_1_endTime_1") {
+                db.collection::<Document>(collection).drop_index("pool_1_endTime_1").await?;
+            }
+        }
+
+        for collection in NETWORK_INTERVAL_COLLECTIONS {
+            unique_index(&db.collection::<Document>(collection), &["granularity", "endTime"]).await?;
+        }
+
+        // Depth documents written before they were tagged with their pool were all BTC.BTC
+        depth_collection
+            .update_many(doc! { "pool": { "$exists": false } }, doc! { "$set": { "pool": "BTC.BTC" } })
+            .await?;
+        unique_index(&depth_collection, &["pool", "granularity", "endTime"]).await?;
+
+        // Swaps documents written before swaps were fetched per pool are network-wide totals
+        swaps_collection
+            .update_many(doc! { "pool": { "$exists": false } }, doc! { "$set": { "pool": ALL_POOLS } })
+            .await?;
+        unique_index(&swaps_collection, &["pool", "granularity", "endTime"]).await?;
+        action_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "txId": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+        pool_earnings_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "pool": 1, "startTime": 1, "endTime": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+        history_meta_collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "series": 1, "start_time": 1, "end_time": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await?;
+        unique_index(&pool_snapshot_collection, &["asset", "snapshotTime"]).await?;
+        unique_index(&member_collection, &["memberAddress", "pool", "snapshotTime"]).await?;
+        unique_index(&runepool_provider_collection, &["memberAddress", "runeAddress", "snapshotTime"]).await?;
+        unique_index(&saver_collection, &["memberAddress", "pool", "snapshotTime"]).await?;
+
+        for (collection, fields) in AMOUNT_FIELDS {
+            let set: Document = fields.iter().map(|field| (field.to_string(), doc! { "$toDecimal": format!("${}", field) }.into())).collect();
+            db.collection::<Document>(collection)
+                .update_many(doc! { fields[0]: { "$type": "string" } }, vec![doc! { "$set": set }])
+                .await?;
+        }
+
+        // Counts were stored as strings; a fractional one fails the migration instead of being rounded
+        let fractional: Vec<Document> = COUNT_FIELDS
+            .iter()
+            .map(|field| doc! { *field: { "$type": "string", "$not": Regex { pattern: "^-?[0-9]+$".to_string(), options: String::new() } } })
+            .collect();
+        if db.collection::<Document>("swaps_intervals").count_documents(doc! { "$or": fractional }).await? > 0 {
+            return Err("swaps_intervals holds counts that are not whole numbers".into());
+        }
+        let set: Document = COUNT_FIELDS.iter().map(|field| (field.to_string(), doc! { "$toLong": format!("${}", field) }.into())).collect();
+        db.collection::<Document>("swaps_intervals")
+            .update_many(doc! { COUNT_FIELDS[0]: { "$type": "string" } }, vec![doc! { "$set": set }])
+            .await?;
+
+        // Unpacks the pools of the earnings intervals stored before, once, while the collection is still empty
+        if pool_earnings_collection.estimated_document_count().await? == 0 {
+            let amount = |field: &str| doc! { "$toDecimal": format!("$pools.{}", field) };
+            db.collection::<Document>("earnings_intervals")
+                .aggregate(vec![
+                    doc! { "$unwind": "$pools" },
+                    doc! { "$project": {
+                        "_id": 0,
+                        "pool": "$pools.pool",
+                        "granularity": 1,
+                        "startTime": { "$toLong": "$startTime" },
+                        "endTime": { "$toLong": "$endTime" },
+                        "assetLiquidityFees": amount("assetLiquidityFees"),
+                        "earnings": amount("earnings"),
+                        "rewards": amount("rewards"),
+                        "runeLiquidityFees": amount("runeLiquidityFees"),
+                        "saverEarning": amount("saverEarning"),
+                        "totalLiquidityFeesRune": amount("totalLiquidityFeesRune"),
+                    } },
+                    doc! { "$merge": {
+                        "into": "pool_earnings",
+                        "on": ["pool", "startTime", "endTime"],
+                        "whenMatched": "keepExisting",
+                        "whenNotMatched": "insert",
+                    } },
+                ])
+                .await?;
+        }
+
+        let hello = db.run_command(doc! { "hello": 1 }).await?;
+        let transactions = hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid");
+        if !transactions {
+            eprintln!("MongoDB is a standalone server; earnings intervals and their pool earnings are written without a transaction");
+        }
 
         Ok(Self {
             client,
             depth_collection,
             swaps_collection,
             earnings_collection,
+            pool_earnings_collection,
             rune_collection,
+            tvl_collection,
+            pool_snapshot_collection,
+            action_collection,
+            member_collection,
+            history_meta_collection,
+            runepool_provider_collection,
+            saver_collection,
+            checkpoint_collection,
+            dead_letter_collection,
+            transactions,
         })
     }
 }
@@ -37,74 +292,319 @@ impl MongoDb {
 impl Database for MongoDb {
     async fn store_depth_intervals(&self , interval: DepthInterval) -> Result<std::time::Duration , Box<dyn Error>>{
         let start_time = Instant::now();
-        self.depth_collection.insert_one(interval).await.expect("Couldn't insert into depth_intervals");
+        let pool = interval.pool.as_ref().ok_or("depth interval is not tagged with its pool")?;
+        writes(&self.depth_collection)
+            .replace_one(
+                doc! { "pool": pool.to_string(), "granularity": interval.granularity.as_str(), "endTime": interval.end_time.to_string() },
+                with_decimals(&interval)?,
+            )
+            .upsert(true)
+            .await?;
         let duration = start_time.elapsed();
         Ok(duration) 
     }
     async fn store_swaps_intervals(&self , interval: SwapsInterval) -> Result<std::time::Duration , Box<dyn Error>>{
         let start_time = Instant::now();
-        self.swaps_collection.insert_one(interval).await.expect("Couldn't insert into swaps_intervals");
+        writes(&self.swaps_collection)
+            .replace_one(
+                doc! { "pool": interval.pool.to_string(), "granularity": interval.granularity.as_str(), "endTime": interval.end_time.to_string() },
+                with_decimals(&interval)?,
+            )
+            .upsert(true)
+            .await?;
         let duration = start_time.elapsed(); 
         Ok(duration)
     }
     async fn store_earnings_intervals(&self , interval: EarningInterval) -> Result<std::time::Duration , Box<dyn Error>>{
         let start_time = Instant::now();
-        self.earnings_collection.insert_one(interval).await.expect("Couldn't insert into earnings_intervals");
+        let pool_earnings = interval.pool_earnings();
+        let mut session = self.client.start_session().await?;
+        if self.transactions {
+            session.start_transaction().await?;
+        }
+        // Dropping the session before the commit aborts the transaction
+        writes(&self.earnings_collection)
+            .replace_one(doc! { "granularity": interval.granularity.as_str(), "endTime": interval.end_time.to_string() }, with_decimals(&interval)?)
+            .upsert(true)
+            .session(&mut session)
+            .await?;
+        for row in &pool_earnings {
+            writes(&self.pool_earnings_collection)
+                .replace_one(doc! { "pool": row.pool.to_string(), "startTime": row.start_time, "endTime": row.end_time }, with_decimals(row)?)
+                .upsert(true)
+                .session(&mut session)
+                .await?;
+        }
+        if self.transactions {
+            session.commit_transaction().await?;
+        }
         let duration = start_time.elapsed();
         Ok(duration)
     }
     async fn store_runepool_intervals(&self , interval: RunePoolInterval) -> Result<std::time::Duration , Box<dyn Error>>{
         let start_time = Instant::now();
-        self.rune_collection.insert_one(interval).await.expect("Couldn't insert into rune_intervals");
+        writes(&self.rune_collection)
+            .replace_one(doc! { "granularity": interval.granularity.as_str(), "endTime": interval.end_time.to_string() }, with_decimals(&interval)?)
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn store_tvl_intervals(&self , interval: TvlInterval) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.tvl_collection)
+            .replace_one(doc! { "granularity": interval.granularity.as_str(), "endTime": interval.end_time.to_string() }, with_decimals(&interval)?)
+            .upsert(true)
+            .await?;
         let duration = start_time.elapsed();
         Ok(duration)
     }
     async fn read_depth_intervals(&self) -> Result<(Vec<DepthInterval>, std::time::Duration), Box<dyn Error>> {
         let start_time = Instant::now();
-        let cursor = self.depth_collection.find(None).await?;
+        let cursor = self.depth_collection.find(doc! {}).await?;
         let depth_intervals: Vec<DepthInterval> = cursor
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .collect(); 
+            .collect::<Result<_, _>>()?;
 
         let duration = start_time.elapsed();
         Ok((depth_intervals, duration))
     }
     async fn read_swaps_intervals(&self) -> Result<(Vec<SwapsInterval>, std::time::Duration), Box<dyn Error>> {
         let start_time = Instant::now();
-        let cursor = self.swaps_collection.find(None).await?;
+        let cursor = self.swaps_collection.find(doc! {}).await?;
         let swaps_intervals: Vec<SwapsInterval> = cursor
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .collect(); 
+            .collect::<Result<_, _>>()?;
 
         let duration = start_time.elapsed();
         Ok((swaps_intervals, duration))
     }
     async fn read_earnings_intervals(&self) -> Result<(Vec<EarningInterval>, std::time::Duration), Box<dyn Error>> {
         let start_time = Instant::now();
-        let cursor = self.earnings_collection.find(None).await?;
+        let cursor = self.earnings_collection.find(doc! {}).await?;
         let earnings_intervals: Vec<EarningInterval> = cursor
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .collect(); 
+            .collect::<Result<_, _>>()?;
 
         let duration = start_time.elapsed();
         Ok((earnings_intervals, duration))
     }
+    async fn read_pool_earnings(&self , pool: &Asset , granularity: Interval) -> Result<(Vec<PoolEarnings>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.pool_earnings_collection
+            .find(doc! { "pool": pool.to_string(), "granularity": granularity.as_str() })
+            .sort(doc! { "startTime": 1 })
+            .await?;
+        let pool_earnings: Vec<PoolEarnings> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((pool_earnings, duration))
+    }
     async fn read_runepool_intervals(&self) -> Result<(Vec<RunePoolInterval>, std::time::Duration), Box<dyn Error>> {
         let start_time = Instant::now();
-        let cursor = self.rune_collection.find(None).await?;
+        let cursor = self.rune_collection.find(doc! {}).await?;
         let rune_pool_intervals: Vec<RunePoolInterval> = cursor
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .collect(); 
+            .collect::<Result<_, _>>()?;
 
         let duration = start_time.elapsed();
         Ok((rune_pool_intervals, duration))
     }
-}
\ No newline at end of file
+    async fn read_tvl_intervals(&self) -> Result<(Vec<TvlInterval>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.tvl_collection.find(doc! {}).await?;
+        let tvl_intervals: Vec<TvlInterval> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((tvl_intervals, duration))
+    }
+    async fn store_pool_snapshot(&self , pool: PoolDetail) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.pool_snapshot_collection)
+            .replace_one(doc! { "asset": pool.asset.to_string(), "snapshotTime": pool.snapshot_time }, with_decimals(&pool)?)
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_pool_snapshots(&self) -> Result<(Vec<PoolDetail>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.pool_snapshot_collection.find(doc! {}).await?;
+        let pools: Vec<PoolDetail> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((pools, duration))
+    }
+    async fn store_action(&self , action: Action) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.action_collection)
+            .replace_one(doc! { "txId": &action.tx_id }, with_decimals(&action)?)
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_actions(&self) -> Result<(Vec<Action>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.action_collection.find(doc! {}).await?;
+        let actions: Vec<Action> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((actions, duration))
+    }
+    async fn store_member_position(&self , position: MemberPool) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.member_collection)
+            .replace_one(
+                doc! { "memberAddress": &position.member_address, "pool": position.pool.to_string(), "snapshotTime": position.snapshot_time },
+                with_decimals(&position)?,
+            )
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_member_positions(&self) -> Result<(Vec<MemberPool>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.member_collection.find(doc! {}).await?;
+        let positions: Vec<MemberPool> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((positions, duration))
+    }
+    async fn store_history_meta(&self , record: HistoryMetaRecord) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        self.history_meta_collection
+            .replace_one(doc! { "series": &record.series, "start_time": record.start_time, "end_time": record.end_time }, &record)
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_history_metas(&self) -> Result<(Vec<HistoryMetaRecord>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.history_meta_collection.find(doc! {}).await?;
+        let records: Vec<HistoryMetaRecord> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((records, duration))
+    }
+    async fn store_runepool_provider(&self , provider: RunePoolProvider) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.runepool_provider_collection)
+            .replace_one(
+                doc! { "memberAddress": &provider.member_address, "runeAddress": &provider.rune_address, "snapshotTime": provider.snapshot_time },
+                with_decimals(&provider)?,
+            )
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_runepool_providers(&self) -> Result<(Vec<RunePoolProvider>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.runepool_provider_collection.find(doc! {}).await?;
+        let providers: Vec<RunePoolProvider> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((providers, duration))
+    }
+    async fn store_saver_position(&self , position: SaverPosition) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        writes(&self.saver_collection)
+            .replace_one(
+                doc! { "memberAddress": &position.member_address, "pool": position.pool.to_string(), "snapshotTime": position.snapshot_time },
+                with_decimals(&position)?,
+            )
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_saver_positions(&self) -> Result<(Vec<SaverPosition>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.saver_collection.find(doc! {}).await?;
+        let positions: Vec<SaverPosition> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((positions, duration))
+    }
+    async fn store_dead_letter(&self , dead_letter: DeadLetter) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        self.dead_letter_collection.insert_one(dead_letter).await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_dead_letters(&self) -> Result<(Vec<DeadLetter>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let cursor = self.dead_letter_collection.find(doc! {}).await?;
+        let dead_letters: Vec<DeadLetter> = cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let duration = start_time.elapsed();
+        Ok((dead_letters, duration))
+    }
+    async fn store_checkpoint(&self , checkpoint: Checkpoint) -> Result<std::time::Duration , Box<dyn Error>>{
+        let start_time = Instant::now();
+        self.checkpoint_collection
+            .replace_one(doc! { "series": &checkpoint.series }, &checkpoint)
+            .upsert(true)
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+    async fn read_checkpoint(&self , series: &str) -> Result<(Option<Checkpoint>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let checkpoint = self.checkpoint_collection.find_one(doc! { "series": series }).await?;
+        let duration = start_time.elapsed();
+        Ok((checkpoint, duration))
+    }
+    // Waits for in-progress operations and closes the connection pools
+    async fn close(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.client.shutdown().await;
+        Ok(())
+    }
+}
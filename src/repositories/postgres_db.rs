@@ -1,31 +1,405 @@
 use async_trait::async_trait;
+use bytes::BytesMut;
 use native_tls::TlsConnector;
 use postgres_native_tls::MakeTlsConnector;
 use std::{error::Error, time::Instant};
-use tokio_postgres::Client;
+use tokio::task::JoinHandle;
+use rust_decimal::Decimal;
+use tokio_postgres::{
+    types::{to_sql_checked, FromSql, IsNull, ToSql, Type},
+    Client,
+};
 
 use crate::{
-    models::{DepthInterval, EarningInterval, RunePoolInterval, SwapsInterval,Pool},
+    models::{Action, Amount, AnyAsset, Asset, Checkpoint, Interval, DeadLetter, DepthInterval, EarningInterval, HistoryMetaRecord, MemberPool, PoolDetail, PoolScope, PoolEarnings, RunePoolInterval, RunePoolProvider, SaverPosition, SwapsInterval, TvlInterval, Pool, PoolDepth},
     services::db_traits::Database,
 };
 
+// Amounts are stored as NUMERIC, which holds them exactly
+impl ToSql for Amount {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <Decimal as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Amount {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Decimal::from_sql(ty, raw).map(Amount)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <Decimal as FromSql>::accepts(ty)
+    }
+}
+
+// Granularities are stored as TEXT in Midgard's notation, e.g. `hour`
+impl ToSql for Interval {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.as_str().to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Interval {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(<&str>::from_sql(ty, raw)?.parse::<Interval>()?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+// Assets are stored as TEXT in Midgard's notation
+impl ToSql for Asset {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.to_string().to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <String as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Asset {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(<&str>::from_sql(ty, raw)?.parse()?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+impl ToSql for AnyAsset {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.to_string().to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <String as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for AnyAsset {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(<&str>::from_sql(ty, raw)?.parse()?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+impl ToSql for PoolScope {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.to_string().to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <String as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for PoolScope {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(<&str>::from_sql(ty, raw)?.parse()?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+// Tables owned by the ingester plus in-place upgrades of older layouts; safe to run on every start
+const SCHEMA: &str = "
+-- Migration helpers used to be created in the default schema, they now live in pg_temp
+DROP FUNCTION IF EXISTS drop_unique_on(TEXT, TEXT);
+DROP FUNCTION IF EXISTS counts_to_bigint(TEXT, VARIADIC TEXT[]);
+DROP FUNCTION IF EXISTS amounts_to_numeric(TEXT, VARIADIC TEXT[]);
+
+-- Drops every unique constraint or index covering exactly `col` on `tbl`, used when a
+-- uniqueness key is widened. Names are looked up because the original tables were created by hand.
+CREATE OR REPLACE FUNCTION pg_temp.drop_unique_on(tbl TEXT, col TEXT) RETURNS VOID AS $$
+DECLARE obj TEXT;
+BEGIN
+    FOR obj IN
+        SELECT c.conname FROM pg_constraint c
+        JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+        WHERE c.conrelid = tbl::regclass AND c.contype IN ('u', 'p')
+            AND array_length(c.conkey, 1) = 1 AND a.attname = col
+    LOOP
+        EXECUTE format('ALTER TABLE %I DROP CONSTRAINT %I', tbl, obj);
+    END LOOP;
+    FOR obj IN
+        SELECT i.relname FROM pg_index x
+        JOIN pg_class i ON i.oid = x.indexrelid
+        JOIN pg_attribute a ON a.attrelid = x.indrelid AND a.attnum = x.indkey[0]
+        WHERE x.indrelid = tbl::regclass AND x.indisunique AND x.indnatts = 1 AND a.attname = col
+    LOOP
+        EXECUTE format('DROP INDEX %I', obj);
+    END LOOP;
+END $$ LANGUAGE plpgsql;
+
+-- Converts the listed columns of `tbl` to BIGINT, skipping ones that already are. Counts
+-- were stored as DOUBLE PRECISION; a fractional one fails the migration instead of being rounded.
+CREATE OR REPLACE FUNCTION pg_temp.counts_to_bigint(tbl TEXT, VARIADIC cols TEXT[]) RETURNS VOID AS $$
+DECLARE col TEXT; fractional BOOLEAN;
+BEGIN
+    FOR col IN
+        SELECT c.column_name FROM information_schema.columns c
+        WHERE c.table_schema = current_schema() AND c.table_name = tbl
+            AND c.column_name = ANY(cols) AND c.data_type <> 'bigint'
+    LOOP
+        EXECUTE format('SELECT EXISTS (SELECT 1 FROM %I WHERE %I <> trunc(%I))', tbl, col, col) INTO fractional;
+        IF fractional THEN
+            RAISE EXCEPTION '%.% holds counts that are not whole numbers', tbl, col;
+        END IF;
+        EXECUTE format('ALTER TABLE %I ALTER COLUMN %I TYPE BIGINT USING %I::BIGINT', tbl, col, col);
+    END LOOP;
+END $$ LANGUAGE plpgsql;
+
+-- Converts the listed columns of `tbl` to NUMERIC, skipping ones that already are and
+-- tables that do not exist (some were created by hand)
+CREATE OR REPLACE FUNCTION pg_temp.amounts_to_numeric(tbl TEXT, VARIADIC cols TEXT[]) RETURNS VOID AS $$
+DECLARE col TEXT;
+BEGIN
+    FOR col IN
+        SELECT c.column_name FROM information_schema.columns c
+        WHERE c.table_schema = current_schema() AND c.table_name = tbl
+            AND c.column_name = ANY(cols) AND c.data_type <> 'numeric'
+    LOOP
+        EXECUTE format('ALTER TABLE %I ALTER COLUMN %I TYPE NUMERIC USING %I::NUMERIC', tbl, col, col);
+    END LOOP;
+END $$ LANGUAGE plpgsql;
+
+CREATE TABLE IF NOT EXISTS checkpoints (series TEXT PRIMARY KEY, end_time BIGINT NOT NULL);
+ALTER TABLE checkpoints ADD COLUMN IF NOT EXISTS page_token TEXT;
+
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id BIGSERIAL PRIMARY KEY, source TEXT NOT NULL, error TEXT NOT NULL, raw JSONB NOT NULL, rejected_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS depthinterval (
+    pool TEXT NOT NULL, granularity TEXT NOT NULL,
+    asset_depth NUMERIC, asset_price DOUBLE PRECISION, asset_price_usd DOUBLE PRECISION,
+    end_time BIGINT NOT NULL, liquidity_units NUMERIC, luvi DOUBLE PRECISION, members_count BIGINT,
+    rune_depth NUMERIC, start_time BIGINT, synth_supply NUMERIC, synth_units NUMERIC, units NUMERIC
+);
+-- Rows written before depths were tagged with their pool were all BTC.BTC
+ALTER TABLE depthinterval ADD COLUMN IF NOT EXISTS pool TEXT NOT NULL DEFAULT 'BTC.BTC';
+ALTER TABLE depthinterval ALTER COLUMN pool DROP DEFAULT;
+-- end_time alone is no longer unique once several pools are stored
+SELECT pg_temp.drop_unique_on('depthinterval', 'end_time');
+-- Rows written before intervals were tagged with their granularity were all hourly
+ALTER TABLE depthinterval ADD COLUMN IF NOT EXISTS granularity TEXT NOT NULL DEFAULT 'hour';
+ALTER TABLE depthinterval ALTER COLUMN granularity DROP DEFAULT;
+-- Hourly and daily intervals of a pool can end at the same time
+DROP INDEX IF EXISTS depthinterval_pool_end_time_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS depthinterval_pool_granularity_end_time_idx ON depthinterval (pool, granularity, end_time);
+
+CREATE TABLE IF NOT EXISTS swapsinterval (
+    pool TEXT NOT NULL, granularity TEXT NOT NULL,
+    average_slip DOUBLE PRECISION, end_time BIGINT NOT NULL, from_trade_average_slip DOUBLE PRECISION, from_trade_count BIGINT,
+    from_trade_fees NUMERIC, from_trade_volume NUMERIC, from_trade_volume_usd NUMERIC, rune_price_usd DOUBLE PRECISION,
+    start_time BIGINT, synth_mint_average_slip DOUBLE PRECISION, synth_mint_count BIGINT, synth_mint_fees NUMERIC,
+    synth_mint_volume NUMERIC, synth_mint_volume_usd NUMERIC, synth_redeem_average_slip DOUBLE PRECISION, synth_redeem_count BIGINT,
+    synth_redeem_fees NUMERIC, synth_redeem_volume NUMERIC, synth_redeem_volume_usd NUMERIC, to_asset_average_slip DOUBLE PRECISION,
+    to_asset_count BIGINT, to_asset_fees NUMERIC, to_asset_volume NUMERIC, to_asset_volume_usd NUMERIC,
+    to_rune_average_slip DOUBLE PRECISION, to_rune_count BIGINT, to_rune_fees NUMERIC, to_rune_volume NUMERIC,
+    to_rune_volume_usd NUMERIC, total_count BIGINT, total_fees NUMERIC, total_volume NUMERIC,
+    total_volume_usd NUMERIC
+);
+-- Rows written before swaps were fetched per pool are network-wide totals
+ALTER TABLE swapsinterval ADD COLUMN IF NOT EXISTS pool TEXT NOT NULL DEFAULT 'all';
+ALTER TABLE swapsinterval ALTER COLUMN pool DROP DEFAULT;
+SELECT pg_temp.drop_unique_on('swapsinterval', 'end_time');
+ALTER TABLE swapsinterval ADD COLUMN IF NOT EXISTS granularity TEXT NOT NULL DEFAULT 'hour';
+ALTER TABLE swapsinterval ALTER COLUMN granularity DROP DEFAULT;
+DROP INDEX IF EXISTS swapsinterval_pool_end_time_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS swapsinterval_pool_granularity_end_time_idx ON swapsinterval (pool, granularity, end_time);
+
+CREATE TABLE IF NOT EXISTS tvlinterval (
+    granularity TEXT NOT NULL, end_time BIGINT NOT NULL, pools_depth JSONB, rune_price_usd DOUBLE PRECISION, start_time BIGINT,
+    total_value_bonded NUMERIC, total_value_locked NUMERIC, total_value_pooled NUMERIC
+);
+ALTER TABLE tvlinterval ADD COLUMN IF NOT EXISTS granularity TEXT NOT NULL DEFAULT 'hour';
+ALTER TABLE tvlinterval ALTER COLUMN granularity DROP DEFAULT;
+SELECT pg_temp.drop_unique_on('tvlinterval', 'end_time');
+CREATE UNIQUE INDEX IF NOT EXISTS tvlinterval_granularity_end_time_idx ON tvlinterval (granularity, end_time);
+
+-- Fields Midgard sent that the models do not know yet, see `SchemaDrift`
+ALTER TABLE depthinterval ADD COLUMN IF NOT EXISTS extra JSONB;
+ALTER TABLE swapsinterval ADD COLUMN IF NOT EXISTS extra JSONB;
+ALTER TABLE tvlinterval ADD COLUMN IF NOT EXISTS extra JSONB;
+
+-- Used to be created by hand, so existing ones may lack the columns added below
+CREATE TABLE IF NOT EXISTS earninginterval (
+    granularity TEXT NOT NULL, avg_node_count DOUBLE PRECISION, block_rewards NUMERIC, bonding_earnings NUMERIC,
+    earnings NUMERIC, end_time BIGINT NOT NULL, liquidity_earnings NUMERIC, liquidity_fees NUMERIC,
+    rune_price_usd DOUBLE PRECISION, start_time BIGINT, pools JSONB
+);
+CREATE TABLE IF NOT EXISTS runepoolinterval (
+    granularity TEXT NOT NULL, count BIGINT, end_time BIGINT NOT NULL, start_time BIGINT, units NUMERIC
+);
+ALTER TABLE earninginterval ADD COLUMN IF NOT EXISTS extra JSONB;
+ALTER TABLE runepoolinterval ADD COLUMN IF NOT EXISTS extra JSONB;
+
+-- Hand-made tables kept the per-pool breakdown as TEXT
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns c
+        WHERE c.table_schema = current_schema() AND c.table_name = 'earninginterval'
+            AND c.column_name = 'pools' AND c.data_type <> 'jsonb'
+    ) THEN
+        ALTER TABLE earninginterval ALTER COLUMN pools TYPE JSONB USING pools::JSONB;
+    END IF;
+END $$;
+
+-- Same granularity upgrade as the other intervals
+DO $$
+DECLARE tbl TEXT;
+BEGIN
+    FOREACH tbl IN ARRAY ARRAY['earninginterval', 'runepoolinterval'] LOOP
+        EXECUTE format('ALTER TABLE %I ADD COLUMN IF NOT EXISTS granularity TEXT NOT NULL DEFAULT ''hour''', tbl);
+        EXECUTE format('ALTER TABLE %I ALTER COLUMN granularity DROP DEFAULT', tbl);
+        PERFORM pg_temp.drop_unique_on(tbl, 'end_time');
+        EXECUTE format('CREATE UNIQUE INDEX IF NOT EXISTS %I ON %I (granularity, end_time)', tbl || '_granularity_end_time_idx', tbl);
+    END LOOP;
+END $$;
+
+-- Per-pool rows of `earninginterval.pools`, so a pool's earnings can be queried directly
+CREATE TABLE IF NOT EXISTS pool_earnings (
+    pool TEXT NOT NULL, start_time BIGINT NOT NULL, end_time BIGINT NOT NULL, granularity TEXT NOT NULL,
+    asset_liquidity_fees NUMERIC, earnings NUMERIC, rewards NUMERIC, rune_liquidity_fees NUMERIC,
+    saver_earning NUMERIC, total_liquidity_fees_rune NUMERIC,
+    PRIMARY KEY (pool, start_time, end_time)
+);
+
+-- Unpacks the intervals stored before, once, while the table is still empty
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pool_earnings) THEN
+        INSERT INTO pool_earnings (pool, start_time, end_time, granularity, asset_liquidity_fees, earnings, rewards, rune_liquidity_fees, saver_earning, total_liquidity_fees_rune)
+        SELECT p->>'pool', e.start_time, e.end_time, e.granularity,
+            (p->>'assetLiquidityFees')::NUMERIC, (p->>'earnings')::NUMERIC, (p->>'rewards')::NUMERIC,
+            (p->>'runeLiquidityFees')::NUMERIC, (p->>'saverEarning')::NUMERIC, (p->>'totalLiquidityFeesRune')::NUMERIC
+        FROM earninginterval e CROSS JOIN LATERAL jsonb_array_elements(e.pools) p
+        ON CONFLICT (pool, start_time, end_time) DO NOTHING;
+    END IF;
+END $$;
+
+CREATE TABLE IF NOT EXISTS actions (
+    tx_id TEXT PRIMARY KEY, date BIGINT NOT NULL, height BIGINT, action_type TEXT, status TEXT,
+    pools TEXT[], in_txs JSONB, out_txs JSONB, metadata JSONB
+);
+CREATE INDEX IF NOT EXISTS actions_date_idx ON actions (date);
+
+CREATE TABLE IF NOT EXISTS member_positions (
+    member_address TEXT NOT NULL, snapshot_time BIGINT NOT NULL, pool TEXT NOT NULL,
+    rune_address TEXT, asset_address TEXT, liquidity_units NUMERIC,
+    rune_added NUMERIC, asset_added NUMERIC, rune_withdrawn NUMERIC, asset_withdrawn NUMERIC,
+    rune_pending NUMERIC, asset_pending NUMERIC, date_first_added BIGINT, date_last_added BIGINT,
+    PRIMARY KEY (member_address, pool, snapshot_time)
+);
+
+
+CREATE TABLE IF NOT EXISTS history_meta (
+    series TEXT NOT NULL, start_time BIGINT NOT NULL, end_time BIGINT NOT NULL,
+    fetched_at BIGINT NOT NULL, meta JSONB NOT NULL,
+    PRIMARY KEY (series, start_time, end_time)
+);
+
+CREATE TABLE IF NOT EXISTS runepool_providers (
+    member_address TEXT NOT NULL, snapshot_time BIGINT NOT NULL, rune_address TEXT NOT NULL, units NUMERIC,
+    rune_added NUMERIC, rune_deposit NUMERIC, rune_withdrawn NUMERIC, date_first_added BIGINT, date_last_added BIGINT,
+    PRIMARY KEY (member_address, rune_address, snapshot_time)
+);
+
+CREATE TABLE IF NOT EXISTS saver_positions (
+    member_address TEXT NOT NULL, snapshot_time BIGINT NOT NULL, pool TEXT NOT NULL, asset_address TEXT,
+    saver_units NUMERIC, asset_added NUMERIC, asset_deposit NUMERIC, asset_redeem NUMERIC, asset_withdrawn NUMERIC,
+    date_first_added BIGINT, date_last_added BIGINT,
+    PRIMARY KEY (member_address, pool, snapshot_time)
+);
+
+CREATE TABLE IF NOT EXISTS pool_snapshots (
+    snapshot_time BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    status TEXT,
+    asset_depth NUMERIC,
+    rune_depth NUMERIC,
+    asset_price DOUBLE PRECISION,
+    asset_price_usd DOUBLE PRECISION,
+    annual_percentage_rate DOUBLE PRECISION,
+    pool_apy DOUBLE PRECISION,
+    earnings NUMERIC,
+    earnings_annual_as_percent_of_depth DOUBLE PRECISION,
+    liquidity_units NUMERIC,
+    synth_units NUMERIC,
+    synth_supply NUMERIC,
+    units NUMERIC,
+    volume24h NUMERIC,
+    savers_depth NUMERIC,
+    savers_units NUMERIC,
+    savers_apr DOUBLE PRECISION,
+    native_decimal BIGINT,
+    PRIMARY KEY (asset, snapshot_time)
+);
+
+-- Amounts used to be BIGINT or DOUBLE PRECISION. BIGINT values convert exactly; DOUBLE PRECISION
+-- values keep whatever precision they still had.
+SELECT pg_temp.amounts_to_numeric('depthinterval', 'asset_depth', 'liquidity_units', 'rune_depth', 'synth_supply', 'synth_units', 'units');
+SELECT pg_temp.amounts_to_numeric('swapsinterval', 'from_trade_fees', 'from_trade_volume', 'from_trade_volume_usd', 'synth_mint_fees', 'synth_mint_volume', 'synth_mint_volume_usd', 'synth_redeem_fees', 'synth_redeem_volume', 'synth_redeem_volume_usd', 'to_asset_fees', 'to_asset_volume', 'to_asset_volume_usd', 'to_rune_fees', 'to_rune_volume', 'to_rune_volume_usd', 'total_fees', 'total_volume', 'total_volume_usd');
+SELECT pg_temp.amounts_to_numeric('tvlinterval', 'total_value_bonded', 'total_value_locked', 'total_value_pooled');
+SELECT pg_temp.amounts_to_numeric('earninginterval', 'block_rewards', 'bonding_earnings', 'earnings', 'liquidity_earnings', 'liquidity_fees');
+SELECT pg_temp.amounts_to_numeric('runepoolinterval', 'units');
+SELECT pg_temp.amounts_to_numeric('pool_snapshots', 'asset_depth', 'rune_depth', 'earnings', 'liquidity_units', 'synth_units', 'synth_supply', 'units', 'volume24h', 'savers_depth', 'savers_units');
+SELECT pg_temp.amounts_to_numeric('member_positions', 'liquidity_units', 'rune_added', 'asset_added', 'rune_withdrawn', 'asset_withdrawn', 'rune_pending', 'asset_pending');
+SELECT pg_temp.amounts_to_numeric('runepool_providers', 'units', 'rune_added', 'rune_deposit', 'rune_withdrawn');
+SELECT pg_temp.amounts_to_numeric('saver_positions', 'saver_units', 'asset_added', 'asset_deposit', 'asset_redeem', 'asset_withdrawn');
+SELECT pg_temp.counts_to_bigint('swapsinterval', 'from_trade_count', 'synth_mint_count', 'synth_redeem_count', 'to_asset_count', 'to_rune_count', 'total_count');
+";
+
 pub struct PostgresDb {
     client: Client,
+    // Drives the connection; finishes once `client` is dropped and its queries are done
+    connection: JoinHandle<()>,
 }
 
 impl PostgresDb {
     pub async fn new(conn: &str) -> Result<Self, Box<dyn Error>> {
         let connector = TlsConnector::builder().build().unwrap();
         let connector = MakeTlsConnector::new(connector);
-        let (client, connection) = tokio_postgres::connect(&conn, connector).await?;
+        let (client, connection) = tokio_postgres::connect(conn, connector).await?;
 
-        tokio::spawn(async move {
+        let connection = tokio::spawn(async move {
             if let Err(e) = connection.await {
                 eprintln!("Database Connection error: {}", e);
             }
         });
 
-        Ok(PostgresDb { client })
+        client.batch_execute(SCHEMA).await?;
+
+        Ok(PostgresDb { client, connection })
     }
 }
 
@@ -36,11 +410,16 @@ impl Database for PostgresDb {
         interval: DepthInterval,
     ) -> Result<std::time::Duration, Box<dyn Error>> {
         println!("inside store depth function");
+        let pool = interval.pool.as_ref().ok_or("depth interval is not tagged with its pool")?;
         let start_time = Instant::now();
-        self.client.execute("INSERT INTO depthinterval (asset_depth, asset_price, asset_price_usd, end_time, liquidity_units, luvi, members_count, rune_depth, start_time, synth_supply, synth_units, units) 
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) ON CONFLICT (end_time) DO NOTHING;
+        self.client.execute("INSERT INTO depthinterval (pool, asset_depth, asset_price, asset_price_usd, end_time, liquidity_units, luvi, members_count, rune_depth, start_time, synth_supply, synth_units, units, extra, granularity) 
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) ON CONFLICT (pool, granularity, end_time) DO UPDATE SET
+            asset_depth = EXCLUDED.asset_depth, asset_price = EXCLUDED.asset_price, asset_price_usd = EXCLUDED.asset_price_usd, liquidity_units = EXCLUDED.liquidity_units, luvi = EXCLUDED.luvi,
+            members_count = EXCLUDED.members_count, rune_depth = EXCLUDED.rune_depth, start_time = EXCLUDED.start_time, synth_supply = EXCLUDED.synth_supply, synth_units = EXCLUDED.synth_units,
+            units = EXCLUDED.units, extra = EXCLUDED.extra;
         ", 
             &[
+                pool,
                 &interval.asset_depth ,
                 &interval.asset_price ,
                 &interval.asset_price_usd ,
@@ -53,6 +432,8 @@ impl Database for PostgresDb {
                 &interval.synth_supply,
                 &interval.synth_units,
                 &interval.units,
+                &interval.extra,
+                &interval.granularity,
             ],
             ).await?;
         let duration = start_time.elapsed(); 
@@ -64,10 +445,18 @@ impl Database for PostgresDb {
         swap: SwapsInterval,
     ) -> Result<std::time::Duration, Box<dyn Error>> {
         let start_time = Instant::now();
-        self.client.execute("INSERT INTO swapsinterval (average_slip, end_time, from_trade_average_slip, from_trade_count, from_trade_fees, from_trade_volume, from_trade_volume_usd, rune_price_usd, start_time, synth_mint_average_slip, synth_mint_count, synth_mint_fees, synth_mint_volume, synth_mint_volume_usd, synth_redeem_average_slip, synth_redeem_count, synth_redeem_fees, synth_redeem_volume, synth_redeem_volume_usd, to_asset_average_slip, to_asset_count, to_asset_fees, to_asset_volume, to_asset_volume_usd, to_rune_average_slip, to_rune_count, to_rune_fees, to_rune_volume, to_rune_volume_usd, total_count, total_fees, total_volume, total_volume_usd) 
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33) ON CONFLICT (end_time) DO NOTHING
+        self.client.execute("INSERT INTO swapsinterval (pool, average_slip, end_time, from_trade_average_slip, from_trade_count, from_trade_fees, from_trade_volume, from_trade_volume_usd, rune_price_usd, start_time, synth_mint_average_slip, synth_mint_count, synth_mint_fees, synth_mint_volume, synth_mint_volume_usd, synth_redeem_average_slip, synth_redeem_count, synth_redeem_fees, synth_redeem_volume, synth_redeem_volume_usd, to_asset_average_slip, to_asset_count, to_asset_fees, to_asset_volume, to_asset_volume_usd, to_rune_average_slip, to_rune_count, to_rune_fees, to_rune_volume, to_rune_volume_usd, total_count, total_fees, total_volume, total_volume_usd, extra, granularity) 
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35, $36) ON CONFLICT (pool, granularity, end_time) DO UPDATE SET
+            average_slip = EXCLUDED.average_slip, from_trade_average_slip = EXCLUDED.from_trade_average_slip, from_trade_count = EXCLUDED.from_trade_count, from_trade_fees = EXCLUDED.from_trade_fees, from_trade_volume = EXCLUDED.from_trade_volume,
+            from_trade_volume_usd = EXCLUDED.from_trade_volume_usd, rune_price_usd = EXCLUDED.rune_price_usd, start_time = EXCLUDED.start_time, synth_mint_average_slip = EXCLUDED.synth_mint_average_slip, synth_mint_count = EXCLUDED.synth_mint_count,
+            synth_mint_fees = EXCLUDED.synth_mint_fees, synth_mint_volume = EXCLUDED.synth_mint_volume, synth_mint_volume_usd = EXCLUDED.synth_mint_volume_usd, synth_redeem_average_slip = EXCLUDED.synth_redeem_average_slip, synth_redeem_count = EXCLUDED.synth_redeem_count,
+            synth_redeem_fees = EXCLUDED.synth_redeem_fees, synth_redeem_volume = EXCLUDED.synth_redeem_volume, synth_redeem_volume_usd = EXCLUDED.synth_redeem_volume_usd, to_asset_average_slip = EXCLUDED.to_asset_average_slip, to_asset_count = EXCLUDED.to_asset_count,
+            to_asset_fees = EXCLUDED.to_asset_fees, to_asset_volume = EXCLUDED.to_asset_volume, to_asset_volume_usd = EXCLUDED.to_asset_volume_usd, to_rune_average_slip = EXCLUDED.to_rune_average_slip, to_rune_count = EXCLUDED.to_rune_count,
+            to_rune_fees = EXCLUDED.to_rune_fees, to_rune_volume = EXCLUDED.to_rune_volume, to_rune_volume_usd = EXCLUDED.to_rune_volume_usd, total_count = EXCLUDED.total_count, total_fees = EXCLUDED.total_fees,
+            total_volume = EXCLUDED.total_volume, total_volume_usd = EXCLUDED.total_volume_usd, extra = EXCLUDED.extra
         ;", 
             &[
+                &swap.pool,
                 &swap.average_slip,
                 &swap.end_time,
                 &swap.from_trade_average_slip,
@@ -101,6 +490,8 @@ impl Database for PostgresDb {
                 &swap.total_fees,
                 &swap.total_volume,
                 &swap.total_volume_usd,
+                &swap.extra,
+                &swap.granularity,
             ],
         ).await?;
         let duration = start_time.elapsed(); 
@@ -115,9 +506,22 @@ impl Database for PostgresDb {
             eprintln!("Error serializing pools: {}", e);
             e
         })?;
+        let pool_earnings = interval.pool_earnings();
         let start_time = Instant::now();
-        self.client.execute("INSERT INTO earninginterval (avg_node_count, block_rewards, bonding_earnings, earnings, end_time, liquidity_earnings, liquidity_fees, rune_price_usd, start_time ,pools) 
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9 , $10) ON CONFLICT (end_time) DO NOTHING
+        // A single statement, so the interval and its pool rows are written in one transaction
+        self.client.execute("WITH earning AS (
+                INSERT INTO earninginterval (avg_node_count, block_rewards, bonding_earnings, earnings, end_time, liquidity_earnings, liquidity_fees, rune_price_usd, start_time ,pools, extra, granularity) 
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9 , $10, $11, $12) ON CONFLICT (granularity, end_time) DO UPDATE SET
+                    avg_node_count = EXCLUDED.avg_node_count, block_rewards = EXCLUDED.block_rewards, bonding_earnings = EXCLUDED.bonding_earnings, earnings = EXCLUDED.earnings, liquidity_earnings = EXCLUDED.liquidity_earnings,
+                    liquidity_fees = EXCLUDED.liquidity_fees, rune_price_usd = EXCLUDED.rune_price_usd, start_time = EXCLUDED.start_time, pools = EXCLUDED.pools, extra = EXCLUDED.extra
+            )
+            INSERT INTO pool_earnings (pool, start_time, end_time, granularity, asset_liquidity_fees, earnings, rewards, rune_liquidity_fees, saver_earning, total_liquidity_fees_rune)
+            SELECT pool, $9, $5, $12, asset_liquidity_fees, earnings, rewards, rune_liquidity_fees, saver_earning, total_liquidity_fees_rune
+            FROM UNNEST($13::TEXT[], $14::NUMERIC[], $15::NUMERIC[], $16::NUMERIC[], $17::NUMERIC[], $18::NUMERIC[], $19::NUMERIC[])
+                AS p (pool, asset_liquidity_fees, earnings, rewards, rune_liquidity_fees, saver_earning, total_liquidity_fees_rune)
+            ON CONFLICT (pool, start_time, end_time) DO UPDATE SET
+                granularity = EXCLUDED.granularity, asset_liquidity_fees = EXCLUDED.asset_liquidity_fees, earnings = EXCLUDED.earnings, rewards = EXCLUDED.rewards, rune_liquidity_fees = EXCLUDED.rune_liquidity_fees,
+                saver_earning = EXCLUDED.saver_earning, total_liquidity_fees_rune = EXCLUDED.total_liquidity_fees_rune
             ;",
                 &[
                     &interval.avg_node_count,
@@ -129,7 +533,16 @@ impl Database for PostgresDb {
                     &interval.liquidity_fees,
                     &interval.rune_price_usd,
                     &interval.start_time,
-                    &pools_json
+                    &pools_json,
+                    &interval.extra,
+                    &interval.granularity,
+                    &pool_earnings.iter().map(|row| &row.pool).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.asset_liquidity_fees).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.earnings).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.rewards).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.rune_liquidity_fees).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.saver_earning).collect::<Vec<_>>(),
+                    &pool_earnings.iter().map(|row| row.total_liquidity_fees_rune).collect::<Vec<_>>(),
                 ],
             ).await?;
         let duration = start_time.elapsed(); 
@@ -143,13 +556,45 @@ impl Database for PostgresDb {
         let start_time = Instant::now();
         self.client
             .execute(
-                "INSERT INTO runepoolinterval (count, end_time, start_time, units) 
-            VALUES ($1, $2, $3, $4) ON CONFLICT (end_time) DO NOTHING;",
+                "INSERT INTO runepoolinterval (count, end_time, start_time, units, extra, granularity) 
+            VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (granularity, end_time) DO UPDATE SET
+                count = EXCLUDED.count, start_time = EXCLUDED.start_time, units = EXCLUDED.units, extra = EXCLUDED.extra;",
             &[
                 &runepool.count,
                 &runepool.end_time,
                 &runepool.start_time,
                 &runepool.units,
+                &runepool.extra,
+                &runepool.granularity,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed(); 
+        Ok(duration)
+    }
+
+    async fn store_tvl_intervals(
+        &self,
+        tvl: TvlInterval,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let pools_depth_json = serde_json::to_value(&tvl.pools_depth)?;
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO tvlinterval (end_time, pools_depth, rune_price_usd, start_time, total_value_bonded, total_value_locked, total_value_pooled, extra, granularity) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (granularity, end_time) DO UPDATE SET
+                pools_depth = EXCLUDED.pools_depth, rune_price_usd = EXCLUDED.rune_price_usd, start_time = EXCLUDED.start_time, total_value_bonded = EXCLUDED.total_value_bonded, total_value_locked = EXCLUDED.total_value_locked,
+                total_value_pooled = EXCLUDED.total_value_pooled, extra = EXCLUDED.extra;",
+            &[
+                &tvl.end_time,
+                &pools_depth_json,
+                &tvl.rune_price_usd,
+                &tvl.start_time,
+                &tvl.total_value_bonded,
+                &tvl.total_value_locked,
+                &tvl.total_value_pooled,
+                &tvl.extra,
+                &tvl.granularity,
                 ],
             )
             .await?;
@@ -168,6 +613,7 @@ impl Database for PostgresDb {
         let mut intervals = Vec::new();
         for row in rows {
             let interval = DepthInterval {
+                pool: row.get("pool"),
                 asset_depth: row.get("asset_depth"),
                 asset_price: row.get("asset_price"),
                 asset_price_usd: row.get("asset_price_usd"),
@@ -180,6 +626,8 @@ impl Database for PostgresDb {
                 synth_supply: row.get("synth_supply"),
                 synth_units: row.get("synth_units"),
                 units: row.get("units"),
+                extra: row.get("extra"),
+                granularity: row.get("granularity"),
             };
             intervals.push(interval);
         }
@@ -198,6 +646,7 @@ impl Database for PostgresDb {
         let swaps_intervals: Vec<SwapsInterval> = rows
             .into_iter()
             .map(|row| SwapsInterval {
+                pool: row.get("pool"),
                 average_slip: row.get("average_slip"),
                 end_time: row.get("end_time"),
                 from_trade_average_slip: row.get("from_trade_average_slip"),
@@ -231,6 +680,8 @@ impl Database for PostgresDb {
                 total_fees: row.get("total_fees"),
                 total_volume: row.get("total_volume"),
                 total_volume_usd: row.get("total_volume_usd"),
+                extra: row.get("extra"),
+                granularity: row.get("granularity"),
             })
             .collect();
         Ok((swaps_intervals, duration))
@@ -241,9 +692,9 @@ impl Database for PostgresDb {
         let rows = self.client.query("SELECT * FROM earninginterval", &[]).await?;
         let duration = start_time.elapsed();
         let earnings_intervals: Vec<EarningInterval> = rows.into_iter().map(|single_row| {  
-            let pools_json: Option<String> = single_row.get("pools");
+            let pools_json: Option<serde_json::Value> = single_row.get("pools");
             let pools: Vec<Pool> = if let Some(json_data) = pools_json {
-                serde_json::from_str(&json_data).unwrap_or_else(|_| Vec::new())  
+                serde_json::from_value(json_data).unwrap_or_else(|_| Vec::new())  
             } else {
                 Vec::new()
             };
@@ -259,11 +710,45 @@ impl Database for PostgresDb {
                 rune_price_usd: single_row.get("rune_price_usd"),
                 start_time: single_row.get("start_time"),
                 pools,  
+                extra: single_row.get("extra"),
+                granularity: single_row.get("granularity"),
             }
         }).collect();
         Ok((earnings_intervals, duration))
     }
 
+    async fn read_pool_earnings(
+        &self,
+        pool: &Asset,
+        granularity: Interval,
+    ) -> Result<(Vec<PoolEarnings>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query(
+                "SELECT * FROM pool_earnings WHERE pool = $1 AND granularity = $2 ORDER BY start_time",
+                &[&pool, &granularity],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        let pool_earnings = rows
+            .into_iter()
+            .map(|row| PoolEarnings {
+                pool: row.get("pool"),
+                granularity: row.get("granularity"),
+                start_time: row.get("start_time"),
+                end_time: row.get("end_time"),
+                asset_liquidity_fees: row.get("asset_liquidity_fees"),
+                earnings: row.get("earnings"),
+                rewards: row.get("rewards"),
+                rune_liquidity_fees: row.get("rune_liquidity_fees"),
+                saver_earning: row.get("saver_earning"),
+                total_liquidity_fees_rune: row.get("total_liquidity_fees_rune"),
+            })
+            .collect();
+        Ok((pool_earnings, duration))
+    }
+
     async fn read_runepool_intervals(
         &self,
     ) -> Result<(Vec<RunePoolInterval>, std::time::Duration), Box<dyn Error>> {
@@ -280,8 +765,473 @@ impl Database for PostgresDb {
                 end_time: row.get("end_time"),
                 start_time: row.get("start_time"),
                 units: row.get("units"),
+                extra: row.get("extra"),
+                granularity: row.get("granularity"),
             })
             .collect();
         Ok((runepool_intervals, duration))
     }
+
+    async fn read_tvl_intervals(
+        &self,
+    ) -> Result<(Vec<TvlInterval>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM tvlinterval", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let tvl_intervals: Vec<TvlInterval> = rows
+            .into_iter()
+            .map(|row| {
+                let pools_depth_json: Option<serde_json::Value> = row.get("pools_depth");
+                let pools_depth: Vec<PoolDepth> = pools_depth_json
+                    .and_then(|json| serde_json::from_value(json).ok())
+                    .unwrap_or_default();
+
+                TvlInterval {
+                    end_time: row.get("end_time"),
+                    pools_depth,
+                    rune_price_usd: row.get("rune_price_usd"),
+                    start_time: row.get("start_time"),
+                    total_value_bonded: row.get("total_value_bonded"),
+                    total_value_locked: row.get("total_value_locked"),
+                    total_value_pooled: row.get("total_value_pooled"),
+                    extra: row.get("extra"),
+                    granularity: row.get("granularity"),
+                }
+            })
+            .collect();
+        Ok((tvl_intervals, duration))
+    }
+
+    async fn store_pool_snapshot(
+        &self,
+        pool: PoolDetail,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO pool_snapshots (snapshot_time, asset, status, asset_depth, rune_depth, asset_price, asset_price_usd, annual_percentage_rate, pool_apy, earnings, earnings_annual_as_percent_of_depth, liquidity_units, synth_units, synth_supply, units, volume24h, savers_depth, savers_units, savers_apr, native_decimal) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20) ON CONFLICT (asset, snapshot_time) DO NOTHING;",
+                &[
+                    &pool.snapshot_time,
+                    &pool.asset,
+                    &pool.status,
+                    &pool.asset_depth,
+                    &pool.rune_depth,
+                    &pool.asset_price,
+                    &pool.asset_price_usd,
+                    &pool.annual_percentage_rate,
+                    &pool.pool_apy,
+                    &pool.earnings,
+                    &pool.earnings_annual_as_percent_of_depth,
+                    &pool.liquidity_units,
+                    &pool.synth_units,
+                    &pool.synth_supply,
+                    &pool.units,
+                    &pool.volume24h,
+                    &pool.savers_depth,
+                    &pool.savers_units,
+                    &pool.savers_apr,
+                    &pool.native_decimal,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_pool_snapshots(
+        &self,
+    ) -> Result<(Vec<PoolDetail>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM pool_snapshots", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let pools: Vec<PoolDetail> = rows
+            .into_iter()
+            .map(|row| PoolDetail {
+                snapshot_time: row.get("snapshot_time"),
+                asset: row.get("asset"),
+                status: row.get("status"),
+                asset_depth: row.get("asset_depth"),
+                rune_depth: row.get("rune_depth"),
+                asset_price: row.get("asset_price"),
+                asset_price_usd: row.get("asset_price_usd"),
+                annual_percentage_rate: row.get("annual_percentage_rate"),
+                pool_apy: row.get("pool_apy"),
+                earnings: row.get("earnings"),
+                earnings_annual_as_percent_of_depth: row.get("earnings_annual_as_percent_of_depth"),
+                liquidity_units: row.get("liquidity_units"),
+                synth_units: row.get("synth_units"),
+                synth_supply: row.get("synth_supply"),
+                units: row.get("units"),
+                volume24h: row.get("volume24h"),
+                savers_depth: row.get("savers_depth"),
+                savers_units: row.get("savers_units"),
+                savers_apr: row.get("savers_apr"),
+                native_decimal: row.get("native_decimal"),
+            })
+            .collect();
+        Ok((pools, duration))
+    }
+
+    async fn store_action(
+        &self,
+        action: Action,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let in_json = serde_json::to_value(&action.in_txs)?;
+        let out_json = serde_json::to_value(&action.out_txs)?;
+        let metadata_json = serde_json::to_value(&action.metadata)?;
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO actions (tx_id, date, height, action_type, status, pools, in_txs, out_txs, metadata) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (tx_id) DO UPDATE SET 
+                date = EXCLUDED.date, height = EXCLUDED.height, action_type = EXCLUDED.action_type, status = EXCLUDED.status, 
+                pools = EXCLUDED.pools, in_txs = EXCLUDED.in_txs, out_txs = EXCLUDED.out_txs, metadata = EXCLUDED.metadata;",
+                &[
+                    &action.tx_id,
+                    &action.date,
+                    &action.height,
+                    &action.action_type,
+                    &action.status,
+                    &action.pools,
+                    &in_json,
+                    &out_json,
+                    &metadata_json,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_actions(
+        &self,
+    ) -> Result<(Vec<Action>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM actions", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let actions = rows
+            .into_iter()
+            .map(|row| -> Result<Action, serde_json::Error> {
+                Ok(Action {
+                    tx_id: row.get("tx_id"),
+                    date: row.get("date"),
+                    height: row.get("height"),
+                    in_txs: serde_json::from_value(row.get("in_txs"))?,
+                    out_txs: serde_json::from_value(row.get("out_txs"))?,
+                    pools: row.get("pools"),
+                    status: row.get("status"),
+                    action_type: row.get("action_type"),
+                    metadata: serde_json::from_value(row.get("metadata"))?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((actions, duration))
+    }
+
+    async fn store_member_position(
+        &self,
+        position: MemberPool,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO member_positions (member_address, snapshot_time, pool, rune_address, asset_address, liquidity_units, rune_added, asset_added, rune_withdrawn, asset_withdrawn, rune_pending, asset_pending, date_first_added, date_last_added) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) ON CONFLICT (member_address, pool, snapshot_time) DO NOTHING;",
+                &[
+                    &position.member_address,
+                    &position.snapshot_time,
+                    &position.pool,
+                    &position.rune_address,
+                    &position.asset_address,
+                    &position.liquidity_units,
+                    &position.rune_added,
+                    &position.asset_added,
+                    &position.rune_withdrawn,
+                    &position.asset_withdrawn,
+                    &position.rune_pending,
+                    &position.asset_pending,
+                    &position.date_first_added,
+                    &position.date_last_added,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_member_positions(
+        &self,
+    ) -> Result<(Vec<MemberPool>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM member_positions", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let positions: Vec<MemberPool> = rows
+            .into_iter()
+            .map(|row| MemberPool {
+                member_address: row.get("member_address"),
+                snapshot_time: row.get("snapshot_time"),
+                pool: row.get("pool"),
+                rune_address: row.get("rune_address"),
+                asset_address: row.get("asset_address"),
+                liquidity_units: row.get("liquidity_units"),
+                rune_added: row.get("rune_added"),
+                asset_added: row.get("asset_added"),
+                rune_withdrawn: row.get("rune_withdrawn"),
+                asset_withdrawn: row.get("asset_withdrawn"),
+                rune_pending: row.get("rune_pending"),
+                asset_pending: row.get("asset_pending"),
+                date_first_added: row.get("date_first_added"),
+                date_last_added: row.get("date_last_added"),
+            })
+            .collect();
+        Ok((positions, duration))
+    }
+
+    async fn store_history_meta(
+        &self,
+        record: HistoryMetaRecord,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO history_meta (series, start_time, end_time, fetched_at, meta) 
+            VALUES ($1, $2, $3, $4, $5) ON CONFLICT (series, start_time, end_time) DO UPDATE SET fetched_at = EXCLUDED.fetched_at, meta = EXCLUDED.meta;",
+                &[
+                    &record.series,
+                    &record.start_time,
+                    &record.end_time,
+                    &record.fetched_at,
+                    &record.meta,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_history_metas(
+        &self,
+    ) -> Result<(Vec<HistoryMetaRecord>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM history_meta", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let records: Vec<HistoryMetaRecord> = rows
+            .into_iter()
+            .map(|row| HistoryMetaRecord {
+                series: row.get("series"),
+                start_time: row.get("start_time"),
+                end_time: row.get("end_time"),
+                fetched_at: row.get("fetched_at"),
+                meta: row.get("meta"),
+            })
+            .collect();
+        Ok((records, duration))
+    }
+
+    async fn store_runepool_provider(
+        &self,
+        provider: RunePoolProvider,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO runepool_providers (member_address, snapshot_time, rune_address, units, rune_added, rune_deposit, rune_withdrawn, date_first_added, date_last_added) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (member_address, rune_address, snapshot_time) DO NOTHING;",
+                &[
+                    &provider.member_address,
+                    &provider.snapshot_time,
+                    &provider.rune_address,
+                    &provider.units,
+                    &provider.rune_added,
+                    &provider.rune_deposit,
+                    &provider.rune_withdrawn,
+                    &provider.date_first_added,
+                    &provider.date_last_added,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_runepool_providers(
+        &self,
+    ) -> Result<(Vec<RunePoolProvider>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM runepool_providers", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let providers: Vec<RunePoolProvider> = rows
+            .into_iter()
+            .map(|row| RunePoolProvider {
+                member_address: row.get("member_address"),
+                snapshot_time: row.get("snapshot_time"),
+                rune_address: row.get("rune_address"),
+                units: row.get("units"),
+                rune_added: row.get("rune_added"),
+                rune_deposit: row.get("rune_deposit"),
+                rune_withdrawn: row.get("rune_withdrawn"),
+                date_first_added: row.get("date_first_added"),
+                date_last_added: row.get("date_last_added"),
+            })
+            .collect();
+        Ok((providers, duration))
+    }
+
+    async fn store_saver_position(
+        &self,
+        position: SaverPosition,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO saver_positions (member_address, snapshot_time, pool, asset_address, saver_units, asset_added, asset_deposit, asset_redeem, asset_withdrawn, date_first_added, date_last_added) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) ON CONFLICT (member_address, pool, snapshot_time) DO NOTHING;",
+                &[
+                    &position.member_address,
+                    &position.snapshot_time,
+                    &position.pool,
+                    &position.asset_address,
+                    &position.saver_units,
+                    &position.asset_added,
+                    &position.asset_deposit,
+                    &position.asset_redeem,
+                    &position.asset_withdrawn,
+                    &position.date_first_added,
+                    &position.date_last_added,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_saver_positions(
+        &self,
+    ) -> Result<(Vec<SaverPosition>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM saver_positions", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let positions: Vec<SaverPosition> = rows
+            .into_iter()
+            .map(|row| SaverPosition {
+                member_address: row.get("member_address"),
+                snapshot_time: row.get("snapshot_time"),
+                pool: row.get("pool"),
+                asset_address: row.get("asset_address"),
+                saver_units: row.get("saver_units"),
+                asset_added: row.get("asset_added"),
+                asset_deposit: row.get("asset_deposit"),
+                asset_redeem: row.get("asset_redeem"),
+                asset_withdrawn: row.get("asset_withdrawn"),
+                date_first_added: row.get("date_first_added"),
+                date_last_added: row.get("date_last_added"),
+            })
+            .collect();
+        Ok((positions, duration))
+    }
+
+    async fn store_dead_letter(
+        &self,
+        dead_letter: DeadLetter,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO dead_letters (source, error, raw, rejected_at) 
+            VALUES ($1, $2, $3, $4);",
+                &[
+                    &dead_letter.source,
+                    &dead_letter.error,
+                    &dead_letter.raw,
+                    &dead_letter.rejected_at,
+                ],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_dead_letters(
+        &self,
+    ) -> Result<(Vec<DeadLetter>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let rows = self
+            .client
+            .query("SELECT * FROM dead_letters", &[])
+            .await?;
+        let duration = start_time.elapsed();
+        let dead_letters: Vec<DeadLetter> = rows
+            .into_iter()
+            .map(|row| DeadLetter {
+                source: row.get("source"),
+                error: row.get("error"),
+                raw: row.get("raw"),
+                rejected_at: row.get("rejected_at"),
+            })
+            .collect();
+        Ok((dead_letters, duration))
+    }
+
+    async fn store_checkpoint(
+        &self,
+        checkpoint: Checkpoint,
+    ) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+        self.client
+            .execute(
+                "INSERT INTO checkpoints (series, end_time, page_token) 
+            VALUES ($1, $2, $3) ON CONFLICT (series) DO UPDATE SET end_time = EXCLUDED.end_time, page_token = EXCLUDED.page_token;",
+                &[&checkpoint.series, &checkpoint.end_time, &checkpoint.page_token],
+            )
+            .await?;
+        let duration = start_time.elapsed();
+        Ok(duration)
+    }
+
+    async fn read_checkpoint(
+        &self,
+        series: &str,
+    ) -> Result<(Option<Checkpoint>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+        let row = self
+            .client
+            .query_opt("SELECT series, end_time, page_token FROM checkpoints WHERE series = $1", &[&series])
+            .await?;
+        let duration = start_time.elapsed();
+        let checkpoint = row.map(|row| Checkpoint {
+            series: row.get("series"),
+            end_time: row.get("end_time"),
+            page_token: row.get("page_token"),
+        });
+        Ok((checkpoint, duration))
+    }
+
+    async fn close(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        let PostgresDb { client, connection } = *self;
+        drop(client);
+        connection.await?;
+        Ok(())
+    }
 }
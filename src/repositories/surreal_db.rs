@@ -1,9 +1,120 @@
-use crate::{models::{DepthInterval, EarningInterval, RunePoolInterval, SwapsInterval}, services::db_traits::Database};
+use crate::{models::{with_native_amounts, Action, ALL_POOLS, Asset, Checkpoint, Interval, DeadLetter, DepthInterval, EarningInterval, HistoryMetaRecord, MemberPool, PoolDetail, PoolEarnings, RunePoolInterval, RunePoolProvider, SaverPosition, SwapsInterval, TvlInterval}, services::db_traits::Database};
+use serde::Serialize;
 use surrealdb::engine::remote::ws::{Client , Ws};
+use surrealdb::sql::Value;
 use surrealdb::opt::auth::Root;
-use surrealdb::Surreal;
-use std::{error::Error, time::Instant};
+use surrealdb::{RecordIdKey, Surreal};
+use std::{convert::Infallible, error::Error, fmt::Display, time::Instant};
 use async_trait::async_trait;
+const INTERVAL_TABLES: [&str; 5] = ["depth_interval", "swaps_interval", "earning_interval", "rune_pool_interval", "tvl_interval"];
+
+// Interval tables that used to be created under random ids, with the id each record is
+// upserted under now as SurrealQL over its fields (see `interval_id` and `network_interval_id`)
+const KEYED_INTERVAL_TABLES: [(&str, &str); 4] = [
+    ("depth_interval", POOL_INTERVAL_KEY),
+    ("swaps_interval", POOL_INTERVAL_KEY),
+    ("earning_interval", NETWORK_INTERVAL_KEY),
+    ("rune_pool_interval", NETWORK_INTERVAL_KEY),
+];
+const POOL_INTERVAL_KEY: &str = "(IF granularity = 'hour' { string::concat(pool, '_', <string> endTime) } ELSE { string::concat(pool, '_', granularity, '_', <string> endTime) })";
+const NETWORK_INTERVAL_KEY: &str = "string::concat(granularity, '_', <string> endTime)";
+
+// Swap counts, stored as strings before they were integers
+const COUNT_FIELDS: [&str; 6] = ["fromTradeCount", "synthMintCount", "synthRedeemCount", "toAssetCount", "toRuneCount", "totalCount"];
+
+// Top-level amount fields of each table, stored as strings before they were decimals. Amounts
+// nested in arrays still read back from strings and are converted when the record is written again.
+const AMOUNT_FIELDS: &[(&str, &[&str])] = &[
+    ("depth_interval", &["assetDepth", "liquidityUnits", "runeDepth", "synthSupply", "synthUnits", "units"]),
+    (
+        "swaps_interval",
+        &[
+            "fromTradeFees",
+            "fromTradeVolume",
+            "fromTradeVolumeUSD",
+            "synthMintFees",
+            "synthMintVolume",
+            "synthMintVolumeUSD",
+            "synthRedeemFees",
+            "synthRedeemVolume",
+            "synthRedeemVolumeUSD",
+            "toAssetFees",
+            "toAssetVolume",
+            "toAssetVolumeUSD",
+            "toRuneFees",
+            "toRuneVolume",
+            "toRuneVolumeUSD",
+            "totalFees",
+            "totalVolume",
+            "totalVolumeUSD",
+        ],
+    ),
+    ("earning_interval", &["blockRewards", "bondingEarnings", "earnings", "liquidityEarnings", "liquidityFees"]),
+    ("rune_pool_interval", &["units"]),
+    ("tvl_interval", &["totalValueBonded", "totalValueLocked", "totalValuePooled"]),
+    (
+        "pool_snapshot",
+        &[
+            "assetDepth",
+            "runeDepth",
+            "earnings",
+            "liquidityUnits",
+            "synthUnits",
+            "synthSupply",
+            "units",
+            "volume24h",
+            "saversDepth",
+            "saversUnits",
+        ],
+    ),
+    (
+        "member_position",
+        &[
+            "liquidityUnits",
+            "runeAdded",
+            "assetAdded",
+            "runeWithdrawn",
+            "assetWithdrawn",
+            "runePending",
+            "assetPending",
+        ],
+    ),
+    ("runepool_provider", &["units", "runeAdded", "runeDeposit", "runeWithdrawn"]),
+    ("saver_position", &["saverUnits", "assetAdded", "assetDeposit", "assetRedeem", "assetWithdrawn"]),
+];
+
+// Serializes a record for `content` with its amounts as native decimals
+fn with_decimals<T: Serialize + 'static>(record: T) -> Result<Value, surrealdb::error::Db> {
+    let record = with_native_amounts(&record, |amount| Ok::<_, Infallible>(Value::from(amount)))
+        .map_err(|e| surrealdb::error::Db::Serialization(e.to_string()))?;
+    surrealdb::sql::to_value(record)
+}
+
+// Record id of a pool's interval. Hourly records keep the ids they had before intervals
+// were tagged with their granularity, so re-fetching them still overwrites.
+fn interval_id(pool: impl Display, granularity: Interval, end_time: i64) -> String {
+    match granularity {
+        Interval::Hour => format!("{}_{}", pool, end_time),
+        other => format!("{}_{}_{}", pool, other, end_time),
+    }
+}
+
+// Record id of a network-wide interval
+fn network_interval_id(granularity: Interval, end_time: i64) -> String {
+    format!("{}_{}", granularity, end_time)
+}
+
+fn tvl_id(granularity: Interval, end_time: i64) -> RecordIdKey {
+    match granularity {
+        Interval::Hour => end_time.into(),
+        other => format!("{}_{}", other, end_time).into(),
+    }
+}
+
+fn pool_earnings_id(row: &PoolEarnings) -> String {
+    format!("{}_{}_{}", row.pool, row.start_time, row.end_time)
+}
+
 pub struct SurrealDB {
     client : Surreal<Client>
 }
@@ -17,6 +128,91 @@ impl SurrealDB {
         }).await?;
         client.use_ns("thor").use_db("mydb").await?;
 
+        // Depth records written before they were tagged with their pool were all BTC.BTC
+        client
+            .query("UPDATE depth_interval SET pool = 'BTC.BTC' WHERE pool = NONE")
+            .await?
+            .check()?;
+        // Swaps records written before swaps were fetched per pool are network-wide totals
+        client
+            .query("UPDATE swaps_interval SET pool = $pool WHERE pool = NONE")
+            .bind(("pool", ALL_POOLS))
+            .await?
+            .check()?;
+
+        // Interval records written before they were tagged with their granularity were all hourly
+        for table in INTERVAL_TABLES {
+            client
+                .query(format!("UPDATE {} SET granularity = 'hour' WHERE granularity = NONE", table))
+                .await?
+                .check()?;
+        }
+
+        // Moves records created under random ids to the ids they are upserted under, so
+        // re-fetching an interval overwrites it; copies of the same interval collapse into one
+        for (table, key) in KEYED_INTERVAL_TABLES {
+            client
+                .query(format!(
+                    "FOR $record IN (SELECT id, {1} AS key FROM {0} WHERE record::id(id) != {1}) {{
+                        UPSERT type::thing('{0}', $record.key) CONTENT (SELECT * OMIT id FROM ONLY $record.id);
+                        DELETE $record.id;
+                    }}",
+                    table, key
+                ))
+                .await?
+                .check()?;
+        }
+
+        for (table, fields) in AMOUNT_FIELDS {
+            let set = fields.iter().map(|field| format!("{0} = <decimal> {0}", field)).collect::<Vec<_>>().join(", ");
+            client
+                .query(format!("UPDATE {} SET {} WHERE type::is::string({})", table, set, fields[0]))
+                .await?
+                .check()?;
+        }
+
+        // Counts were stored as strings; a fractional one fails the migration instead of being cast
+        let fractional = COUNT_FIELDS
+            .iter()
+            .map(|field| format!("(type::is::string({0}) AND !string::is::numeric({0}))", field))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let mut response = client.query(format!("SELECT VALUE id FROM swaps_interval WHERE {}", fractional)).await?.check()?;
+        let fractional: Vec<surrealdb::RecordId> = response.take(0)?;
+        if !fractional.is_empty() {
+            return Err("swaps_interval holds counts that are not whole numbers".into());
+        }
+        let set = COUNT_FIELDS.iter().map(|field| format!("{0} = <int> {0}", field)).collect::<Vec<_>>().join(", ");
+        client
+            .query(format!("UPDATE swaps_interval SET {} WHERE type::is::string({})", set, COUNT_FIELDS[0]))
+            .await?
+            .check()?;
+
+        // Unpacks the pools of the earnings intervals stored before, once, while the table is still empty
+        client
+            .query(
+                "IF array::len((SELECT VALUE id FROM pool_earnings LIMIT 1)) = 0 {
+                    FOR $interval IN (SELECT * FROM earning_interval) {
+                        FOR $pool IN $interval.pools ?? [] {
+                            UPSERT type::thing('pool_earnings', string::concat($pool.pool, '_', <string> $interval.startTime, '_', <string> $interval.endTime)) CONTENT {
+                                pool: $pool.pool,
+                                granularity: $interval.granularity,
+                                startTime: <int> $interval.startTime,
+                                endTime: <int> $interval.endTime,
+                                assetLiquidityFees: <decimal> $pool.assetLiquidityFees,
+                                earnings: <decimal> $pool.earnings,
+                                rewards: <decimal> $pool.rewards,
+                                runeLiquidityFees: <decimal> $pool.runeLiquidityFees,
+                                saverEarning: <decimal> $pool.saverEarning,
+                                totalLiquidityFeesRune: <decimal> $pool.totalLiquidityFeesRune,
+                            };
+                        };
+                    };
+                }",
+            )
+            .await?
+            .check()?;
+
         Ok(SurrealDB { client })
     }
 }
@@ -25,9 +221,12 @@ impl Database for SurrealDB {
     async fn store_depth_intervals(&self, interval: DepthInterval) -> Result<std::time::Duration, Box<dyn Error>> {
         let start_time = Instant::now();
         
+        // One record per pool and interval, so re-fetching a window overwrites instead of duplicating
+        let pool = interval.pool.as_ref().ok_or("depth interval is not tagged with its pool")?;
+        let id = interval_id(pool, interval.granularity, interval.end_time);
         let _out: Option<DepthInterval> = self.client
-        .create("depth_interval")
-        .content(interval)
+        .upsert(("depth_interval", id))
+        .content(with_decimals(interval)?)
         .await?;
 
         Ok(start_time.elapsed())
@@ -36,9 +235,10 @@ impl Database for SurrealDB {
     async fn store_swaps_intervals(&self, swap: SwapsInterval) -> Result<std::time::Duration, Box<dyn Error>> {
         let start_time = Instant::now();
 
+        let id = interval_id(&swap.pool, swap.granularity, swap.end_time);
         let _out : Option<SwapsInterval> = self.client
-            .create("swaps_interval")
-            .content(swap)
+            .upsert(("swaps_interval", id))
+            .content(with_decimals(swap)?)
             .await?;
 
         Ok(start_time.elapsed())
@@ -47,10 +247,24 @@ impl Database for SurrealDB {
     async fn store_earnings_intervals(&self, interval: EarningInterval) -> Result<std::time::Duration, Box<dyn Error>> {
         let start_time = Instant::now();
 
-        let _out : Option<EarningInterval> = self.client
-            .create("earning_interval")
-            .content(interval)
-            .await?;
+        // The interval and its pool rows go in one transaction, each pool row bound by index
+        let pool_earnings = interval.pool_earnings();
+        let mut query = String::from("BEGIN TRANSACTION; UPSERT type::thing('earning_interval', $interval_id) CONTENT $interval;");
+        for i in 0..pool_earnings.len() {
+            query.push_str(&format!(" UPSERT type::thing('pool_earnings', $id{0}) CONTENT $pool{0};", i));
+        }
+        query.push_str(" COMMIT TRANSACTION;");
+
+        let mut request = self.client
+            .query(query)
+            .bind(("interval_id", network_interval_id(interval.granularity, interval.end_time)))
+            .bind(("interval", with_decimals(interval)?));
+        for (i, row) in pool_earnings.into_iter().enumerate() {
+            request = request
+                .bind((format!("id{}", i), pool_earnings_id(&row)))
+                .bind((format!("pool{}", i), with_decimals(row)?));
+        }
+        request.await?.check()?;
 
         Ok(start_time.elapsed())
     }
@@ -58,9 +272,21 @@ impl Database for SurrealDB {
     async fn store_runepool_intervals(&self, runepool: RunePoolInterval) -> Result<std::time::Duration, Box<dyn Error>> {
         let start_time = Instant::now();
 
+        let id = network_interval_id(runepool.granularity, runepool.end_time);
         let _out: Option<RunePoolInterval> = self.client
-            .create("rune_pool_interval")
-            .content(runepool)
+            .upsert(("rune_pool_interval", id))
+            .content(with_decimals(runepool)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn store_tvl_intervals(&self, tvl: TvlInterval) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let _out: Option<TvlInterval> = self.client
+            .upsert(("tvl_interval", tvl_id(tvl.granularity, tvl.end_time)))
+            .content(with_decimals(tvl)?)
             .await?;
 
         Ok(start_time.elapsed())
@@ -102,6 +328,20 @@ impl Database for SurrealDB {
         Ok((result, duration))
     }
 
+    async fn read_pool_earnings(&self, pool: &Asset, granularity: Interval) -> Result<(Vec<PoolEarnings>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<PoolEarnings> = self.client
+            .query("SELECT * FROM pool_earnings WHERE pool = $pool AND granularity = $granularity ORDER BY startTime")
+            .bind(("pool", pool.to_string()))
+            .bind(("granularity", granularity))
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
     async fn read_runepool_intervals(&self) -> Result<(Vec<RunePoolInterval>, std::time::Duration), Box<dyn Error>> {
         let start_time = Instant::now();
 
@@ -113,4 +353,210 @@ impl Database for SurrealDB {
         let duration = start_time.elapsed();
         Ok((result, duration))
     }
-}
\ No newline at end of file
+
+    async fn read_tvl_intervals(&self) -> Result<(Vec<TvlInterval>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<TvlInterval> = self.client
+            .query("SELECT * FROM tvl_interval")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_pool_snapshot(&self, pool: PoolDetail) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let id = format!("{}_{}", pool.asset, pool.snapshot_time);
+        let _out: Option<PoolDetail> = self.client
+            .upsert(("pool_snapshot", id))
+            .content(with_decimals(pool)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_pool_snapshots(&self) -> Result<(Vec<PoolDetail>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<PoolDetail> = self.client
+            .query("SELECT * FROM pool_snapshot")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_action(&self, action: Action) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let _out: Option<Action> = self.client
+            .upsert(("action", action.tx_id.as_str()))
+            .content(with_decimals(action)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_actions(&self) -> Result<(Vec<Action>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<Action> = self.client
+            .query("SELECT * FROM action")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_member_position(&self, position: MemberPool) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let id = format!("{}_{}_{}", position.member_address, position.pool, position.snapshot_time);
+        let _out: Option<MemberPool> = self.client
+            .upsert(("member_position", id))
+            .content(with_decimals(position)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_member_positions(&self) -> Result<(Vec<MemberPool>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<MemberPool> = self.client
+            .query("SELECT * FROM member_position")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_history_meta(&self, record: HistoryMetaRecord) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let id = format!("{}_{}_{}", record.series, record.start_time, record.end_time);
+        let _out: Option<HistoryMetaRecord> = self.client
+            .upsert(("history_meta", id))
+            .content(record)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_history_metas(&self) -> Result<(Vec<HistoryMetaRecord>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<HistoryMetaRecord> = self.client
+            .query("SELECT * FROM history_meta")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_runepool_provider(&self, provider: RunePoolProvider) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let id = format!("{}_{}_{}", provider.member_address, provider.rune_address, provider.snapshot_time);
+        let _out: Option<RunePoolProvider> = self.client
+            .upsert(("runepool_provider", id))
+            .content(with_decimals(provider)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_runepool_providers(&self) -> Result<(Vec<RunePoolProvider>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<RunePoolProvider> = self.client
+            .query("SELECT * FROM runepool_provider")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_saver_position(&self, position: SaverPosition) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let id = format!("{}_{}_{}", position.member_address, position.pool, position.snapshot_time);
+        let _out: Option<SaverPosition> = self.client
+            .upsert(("saver_position", id))
+            .content(with_decimals(position)?)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_saver_positions(&self) -> Result<(Vec<SaverPosition>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<SaverPosition> = self.client
+            .query("SELECT * FROM saver_position")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_dead_letter(&self, dead_letter: DeadLetter) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let _out: Option<DeadLetter> = self.client
+            .create("dead_letter")
+            .content(dead_letter)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_dead_letters(&self) -> Result<(Vec<DeadLetter>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Vec<DeadLetter> = self.client
+            .query("SELECT * FROM dead_letter")
+            .await?
+            .take(0)?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn store_checkpoint(&self, checkpoint: Checkpoint) -> Result<std::time::Duration, Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let _out: Option<Checkpoint> = self.client
+            .upsert(("checkpoint", checkpoint.series.as_str()))
+            .content(checkpoint)
+            .await?;
+
+        Ok(start_time.elapsed())
+    }
+
+    async fn read_checkpoint(&self, series: &str) -> Result<(Option<Checkpoint>, std::time::Duration), Box<dyn Error>> {
+        let start_time = Instant::now();
+
+        let result: Option<Checkpoint> = self.client
+            .select(("checkpoint", series))
+            .await?;
+
+        let duration = start_time.elapsed();
+        Ok((result, duration))
+    }
+
+    async fn close(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        // Ends the signed-in session; dropping the client then closes the socket
+        self.client.invalidate().await?;
+        Ok(())
+    }
+}
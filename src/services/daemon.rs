@@ -0,0 +1,160 @@
+use std::{env, str::FromStr, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    api::api_fetcher::{BackfillRange, MidgardClient},
+    models::{Asset, Interval, TimeWindow},
+    services::{ingester::{Ingester, Series}, shutdown::Shutdown},
+};
+
+// Kinds of history series a schedule can poll; depth and swaps expand to every pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesKind {
+    Depth,
+    Swaps,
+    Earnings,
+    RunePool,
+    Tvl,
+}
+
+impl SeriesKind {
+    pub const ALL: [SeriesKind; 5] = [SeriesKind::Depth, SeriesKind::Swaps, SeriesKind::Earnings, SeriesKind::RunePool, SeriesKind::Tvl];
+}
+
+impl FromStr for SeriesKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depth" => Ok(SeriesKind::Depth),
+            "swaps" => Ok(SeriesKind::Swaps),
+            "earnings" => Ok(SeriesKind::Earnings),
+            "runepool" => Ok(SeriesKind::RunePool),
+            "tvl" => Ok(SeriesKind::Tvl),
+            other => Err(format!("unknown series {:?}", other)),
+        }
+    }
+}
+
+// What to poll at one granularity and how long after each boundary
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub interval: Interval,
+    pub series: Vec<SeriesKind>,
+    // Grace period after an interval closes, so Midgard has indexed its last blocks
+    pub delay: Duration,
+}
+
+impl Schedule {
+    // DAEMON_INTERVALS lists the granularities (default `hour`); each one reads
+    // DAEMON_SERIES_<INTERVAL> (default every series) and DAEMON_DELAY_SECS_<INTERVAL>
+    // (default DAEMON_DELAY_SECS, else 30s), e.g. DAEMON_SERIES_DAY=earnings,tvl
+    pub fn from_env() -> Result<Vec<Self>, String> {
+        let default_delay = env::var("DAEMON_DELAY_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        let intervals = env::var("DAEMON_INTERVALS").unwrap_or_else(|_| "hour".to_string());
+
+        let mut schedules = Vec::new();
+        for interval in intervals.split(',').map(str::trim).filter(|interval| !interval.is_empty()) {
+            let interval = Interval::from_str(interval)?;
+            let suffix = interval.as_str().to_uppercase();
+            let series = match env::var(format!("DAEMON_SERIES_{}", suffix)) {
+                Ok(raw) => raw
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|series| !series.is_empty())
+                    .map(SeriesKind::from_str)
+                    .collect::<Result<_, _>>()?,
+                Err(_) => SeriesKind::ALL.to_vec(),
+            };
+            let delay = env::var(format!("DAEMON_DELAY_SECS_{}", suffix)).ok().and_then(|v| v.parse().ok()).unwrap_or(default_delay);
+            schedules.push(Schedule { interval, series, delay: Duration::from_secs(delay) });
+        }
+        Ok(schedules)
+    }
+}
+
+// Keeps every schedule polling, each on its own interval boundaries
+pub struct Daemon<'a> {
+    midgard: &'a MidgardClient,
+    ingester: &'a Ingester<'a>,
+    // Where series without a checkpoint start
+    from: DateTime<Utc>,
+    // Fixed pool list; every pool from `/pools` (refreshed on each poll) when `None`
+    pools: Option<Vec<Asset>>,
+    shutdown: &'a Shutdown,
+}
+
+impl<'a> Daemon<'a> {
+    pub fn new(midgard: &'a MidgardClient, ingester: &'a Ingester<'a>, from: DateTime<Utc>, pools: Option<Vec<Asset>>, shutdown: &'a Shutdown) -> Self {
+        Self { midgard, ingester, from, pools, shutdown }
+    }
+
+    // Runs every schedule concurrently until shutdown is requested
+    pub async fn run(&self, schedules: &[Schedule]) {
+        futures::future::join_all(schedules.iter().map(|schedule| self.run_schedule(schedule))).await;
+    }
+
+    // Catches up on everything closed so far, then polls shortly after each boundary
+    async fn run_schedule(&self, schedule: &Schedule) {
+        while !self.shutdown.is_requested() {
+            let now = Utc::now();
+            let closed = schedule.interval.bucket_start(now);
+            let Some(next) = schedule.interval.bucket_end(now) else { return };
+            self.poll(schedule, closed).await;
+
+            let wake_at = next.timestamp() + schedule.delay.as_secs() as i64;
+            let sleep_secs = (wake_at - Utc::now().timestamp()).max(0) as u64;
+            println!("Next {} poll in {}s", schedule.interval, sleep_secs);
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(sleep_secs)) => {},
+                _ = self.shutdown.requested() => return,
+            }
+        }
+    }
+
+    async fn pools(&self) -> Vec<Asset> {
+        if let Some(pools) = &self.pools {
+            return pools.clone();
+        }
+        match self.midgard.fetch_pools().await {
+            Ok(pools) => pools.into_iter().map(|pool| pool.asset).collect(),
+            Err(e) => {
+                eprintln!("Failed to fetch pool list: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    // Stores every interval of the schedule's series that closed by `closed`
+    async fn poll(&self, schedule: &Schedule, closed: DateTime<Utc>) {
+        let needs_pools = schedule.series.iter().any(|kind| matches!(kind, SeriesKind::Depth | SeriesKind::Swaps));
+        let pools = if needs_pools { self.pools().await } else { Vec::new() };
+
+        let mut series = Vec::new();
+        for kind in &schedule.series {
+            match kind {
+                SeriesKind::Depth => series.extend(pools.iter().cloned().map(Series::Depth)),
+                SeriesKind::Swaps => {
+                    series.push(Series::Swaps(None));
+                    series.extend(pools.iter().cloned().map(|pool| Series::Swaps(Some(pool))));
+                }
+                SeriesKind::Earnings => series.push(Series::Earnings),
+                SeriesKind::RunePool => series.push(Series::RunePool),
+                SeriesKind::Tvl => series.push(Series::Tvl),
+            }
+        }
+
+        // Nothing closed yet when `from` is still ahead
+        let window = match TimeWindow::from_datetimes(self.from.min(closed), closed) {
+            Ok(window) => window,
+            Err(e) => { eprintln!("Cannot poll {} series up to {}: {}", schedule.interval, closed, e); return; },
+        };
+        let range = BackfillRange { window, interval: schedule.interval };
+        let report = self.ingester.ingest_all(&series, &range).await;
+        println!(
+            "Polled {} {} series up to {}, {} new intervals, {} failed",
+            series.len(), schedule.interval, closed, report.stored(), report.failed.len()
+        );
+    }
+}
@@ -12,7 +12,7 @@ pub enum DbType {
 
 pub fn match_database_type(db_type: &str, args: &[String]) -> Result<DbType, Box<dyn Error>> {
     match db_type {
-        "postgres" if args.len() >= 1 => {
+        "postgres" if !args.is_empty() => {
             let conn_str = args[0].clone(); 
             Ok(DbType::Postgres(conn_str))
         },
@@ -27,7 +27,7 @@ pub fn match_database_type(db_type: &str, args: &[String]) -> Result<DbType, Box
             let password = args[2].clone();
             Ok(DbType::SurrealDb(url, username, password))
         },
-        "rocksdb" if args.len() >= 1 => {
+        "rocksdb" if !args.is_empty() => {
             let path = args[0].clone();
             Ok(DbType::Rocksdb(path))
         },
@@ -50,7 +50,7 @@ impl DatabaseFactory {
                 Ok(Box::new(mongo_db))
                 
             },
-            DbType::Rocksdb(path) => {
+            DbType::Rocksdb(_path) => {
                 // Placeholder for RocksDB implementation
                 // let rocks_db = RocksDb::new(&path)?;
                 // Ok(Box::new(rocks_db))
@@ -1,17 +1,56 @@
 use async_trait::async_trait;
 use std::error::Error;
-use crate::models::{DepthInterval, EarningInterval, RunePoolInterval, SwapsInterval};
+use crate::models::{Action, Asset, Checkpoint, DeadLetter, DepthInterval, EarningInterval, HistoryMetaRecord, Interval, MemberPool, PoolDetail, PoolEarnings, RunePoolInterval, RunePoolProvider, SaverPosition, SwapsInterval, TvlInterval};
 
 
+#[allow(dead_code)]
 #[async_trait]
 pub trait Database: Send + Sync {
+    // Intervals are upserted by pool (where there is one), granularity and end time; storing
+    // one again replaces it
     async fn store_depth_intervals(&self , interval: DepthInterval) -> Result<std::time::Duration , Box<dyn Error>>;
     async fn store_swaps_intervals(&self , interval: SwapsInterval) -> Result<std::time::Duration , Box<dyn Error>>;
+    // Also writes `EarningInterval::pool_earnings`, in the same transaction as the interval
     async fn store_earnings_intervals(&self , interval: EarningInterval) -> Result<std::time::Duration , Box<dyn Error>>;
     async fn store_runepool_intervals(&self , interval: RunePoolInterval) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn store_tvl_intervals(&self , interval: TvlInterval) -> Result<std::time::Duration , Box<dyn Error>>;
 
     async fn read_depth_intervals(&self) -> Result<(Vec<DepthInterval>, std::time::Duration), Box<dyn Error>>;
     async fn read_swaps_intervals(&self) -> Result<(Vec<SwapsInterval>, std::time::Duration), Box<dyn Error>>;
     async fn read_earnings_intervals(&self) -> Result<(Vec<EarningInterval>, std::time::Duration), Box<dyn Error>>;
     async fn read_runepool_intervals(&self) -> Result<(Vec<RunePoolInterval>, std::time::Duration), Box<dyn Error>>;
+    async fn read_tvl_intervals(&self) -> Result<(Vec<TvlInterval>, std::time::Duration), Box<dyn Error>>;
+
+    // Earnings of one pool at one granularity, oldest interval first
+    async fn read_pool_earnings(&self , pool: &Asset , granularity: Interval) -> Result<(Vec<PoolEarnings>, std::time::Duration), Box<dyn Error>>;
+
+    async fn store_pool_snapshot(&self , pool: PoolDetail) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_pool_snapshots(&self) -> Result<(Vec<PoolDetail>, std::time::Duration), Box<dyn Error>>;
+
+    // Upserts by `Action::tx_id`, so re-fetching an action (e.g. once it is no longer pending) replaces it
+    async fn store_action(&self , action: Action) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_actions(&self) -> Result<(Vec<Action>, std::time::Duration), Box<dyn Error>>;
+
+    async fn store_member_position(&self , position: MemberPool) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_member_positions(&self) -> Result<(Vec<MemberPool>, std::time::Duration), Box<dyn Error>>;
+
+    // Upserts by series and window, so re-fetching a page replaces its earlier meta
+    async fn store_history_meta(&self , record: HistoryMetaRecord) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_history_metas(&self) -> Result<(Vec<HistoryMetaRecord>, std::time::Duration), Box<dyn Error>>;
+
+    async fn store_runepool_provider(&self , provider: RunePoolProvider) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_runepool_providers(&self) -> Result<(Vec<RunePoolProvider>, std::time::Duration), Box<dyn Error>>;
+
+    async fn store_saver_position(&self , position: SaverPosition) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_saver_positions(&self) -> Result<(Vec<SaverPosition>, std::time::Duration), Box<dyn Error>>;
+
+    // Append only; every rejected record is kept, even when the same one is rejected again
+    async fn store_dead_letter(&self , dead_letter: DeadLetter) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_dead_letters(&self) -> Result<(Vec<DeadLetter>, std::time::Duration), Box<dyn Error>>;
+
+    async fn store_checkpoint(&self , checkpoint: Checkpoint) -> Result<std::time::Duration , Box<dyn Error>>;
+    async fn read_checkpoint(&self , series: &str) -> Result<(Option<Checkpoint>, std::time::Duration), Box<dyn Error>>;
+
+    // Ends the connection once pending writes are done; called last, on shutdown
+    async fn close(self: Box<Self>) -> Result<(), Box<dyn Error>>;
 }
\ No newline at end of file
@@ -0,0 +1,418 @@
+use std::{collections::BTreeMap, error::Error, future::Future, sync::Mutex, time::Duration};
+
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    api::api_fetcher::{ActionsParams, ApiError, BackfillRange, MidgardClient},
+    models::{Asset, Checkpoint, Interval, DeadLetter, HistoryInterval, HistoryMetaRecord, HistoryResponse},
+    services::{db_traits::Database, shutdown::Shutdown},
+};
+
+// One `/history/*` series; depth and per-pool swaps exist once per pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Series {
+    Depth(Asset),
+    // `None` for the network-wide totals
+    Swaps(Option<Asset>),
+    Earnings,
+    RunePool,
+    Tvl,
+}
+
+impl Series {
+    // Name used for checkpoints, meta records and dead letters
+    pub fn key(&self) -> String {
+        match self {
+            Series::Depth(pool) => format!("depth:{}", pool),
+            Series::Swaps(Some(pool)) => format!("swaps:{}", pool),
+            Series::Swaps(None) => "swaps".to_string(),
+            Series::Earnings => "earnings".to_string(),
+            Series::RunePool => "runepool".to_string(),
+            Series::Tvl => "tvl".to_string(),
+        }
+    }
+}
+
+// Checkpoint of `/actions`: every action up to its `end_time` is stored
+const ACTIONS_CHECKPOINT: &str = "actions";
+// Actions sweep in progress: the newest action it stored and the token of its next page
+const ACTIONS_SWEEP_CHECKPOINT: &str = "actions:sweep";
+
+// Checkpoints are kept per series and granularity
+pub fn checkpoint_key(series: &str, interval: Interval) -> String {
+    format!("{}@{}", series, interval)
+}
+
+// Stored `end_time` of `key`, if any
+pub async fn read_checkpoint_end(db: &dyn Database, key: &str) -> Option<i64> {
+    match db.read_checkpoint(key).await {
+        Ok((checkpoint, _)) => checkpoint.map(|checkpoint| checkpoint.end_time),
+        Err(e) => {
+            eprintln!("Failed to read checkpoint for {}: {}", key, e);
+            None
+        }
+    }
+}
+
+pub async fn save_checkpoint(db: &dyn Database, key: &str, end_time: Option<i64>) {
+    let Some(end_time) = end_time else { return };
+    let checkpoint = Checkpoint { series: key.to_string(), end_time, page_token: None };
+    if let Err(e) = db.store_checkpoint(checkpoint).await {
+        eprintln!("Failed to store checkpoint for {}: {}", key, e);
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum IngestError {
+    #[error(transparent)]
+    Fetch(#[from] ApiError),
+
+    #[error("stored {stored} intervals, then a write failed: {error}")]
+    Store { stored: usize, error: String },
+}
+
+// Per-series outcome of `Ingester::ingest_all`
+#[derive(Debug, Default)]
+pub struct IngestReport {
+    // Intervals stored per series
+    pub succeeded: BTreeMap<String, usize>,
+    // Why each failed series stopped
+    pub failed: BTreeMap<String, String>,
+}
+
+impl IngestReport {
+    pub fn stored(&self) -> usize {
+        self.succeeded.values().sum()
+    }
+
+    pub fn print_summary(&self) {
+        println!("INGESTED {} SERIES, {} FAILED:", self.succeeded.len(), self.failed.len());
+        for (series, stored) in &self.succeeded {
+            println!("  {}: {} intervals", series, stored);
+        }
+        for (series, error) in &self.failed {
+            println!("  {}: FAILED ({})", series, error);
+        }
+    }
+}
+
+// Fetches history series and stores their intervals, page meta and rejects,
+// resuming each series from its checkpoint
+pub struct Ingester<'a> {
+    midgard: &'a MidgardClient,
+    db: &'a dyn Database,
+    shutdown: &'a Shutdown,
+    // Series fetched at once by `ingest_all`
+    concurrency: usize,
+    // Records that could not be parsed, per series, for the end-of-run summary
+    rejected: Mutex<BTreeMap<String, usize>>,
+}
+
+impl<'a> Ingester<'a> {
+    pub fn new(midgard: &'a MidgardClient, db: &'a dyn Database, shutdown: &'a Shutdown, concurrency: usize) -> Self {
+        Self { midgard, db, shutdown, concurrency: concurrency.max(1), rejected: Mutex::new(BTreeMap::new()) }
+    }
+
+    // Moves the start of `range` up to the checkpoint of `series` at `range.interval`.
+    // Checkpoints written before they were kept per granularity have no suffix and were
+    // all hourly, so hourly series fall back to them.
+    async fn resume_range(&self, series: &str, range: &BackfillRange) -> BackfillRange {
+        let mut end_time = read_checkpoint_end(self.db, &checkpoint_key(series, range.interval)).await;
+        if end_time.is_none() && range.interval == Interval::Hour {
+            end_time = read_checkpoint_end(self.db, series).await;
+        }
+        match end_time {
+            Some(end_time) if end_time > range.window.start_time() => {
+                println!("Resuming {} ({}) from checkpoint {}", series, range.interval, end_time);
+                BackfillRange { window: range.window.starting_at(end_time), ..range.clone() }
+            }
+            _ => range.clone(),
+        }
+    }
+
+    // Runs a fetch unless shutdown is requested first; `None` when it was abandoned
+    async fn fetch<T>(&self, fetch: impl Future<Output = Result<T, ApiError>>) -> Result<Option<T>, ApiError> {
+        if self.shutdown.is_requested() {
+            return Ok(None);
+        }
+        tokio::select! {
+            result = fetch => result.map(Some),
+            _ = self.shutdown.requested() => Ok(None),
+        }
+    }
+
+    // Fetches the closed intervals of `series` within `range` that are not stored yet, storing
+    // each page before the next is fetched, and returns how many were stored. Nothing new is
+    // fetched once shutdown is requested.
+    pub async fn ingest(&self, series: &Series, range: &BackfillRange) -> Result<usize, IngestError> {
+        let key = series.key();
+        let checkpoint = checkpoint_key(&key, range.interval);
+        let resumed = self.resume_range(&key, range).await;
+        // Already caught up to the end of the window
+        if resumed.window.is_empty() {
+            return Ok(0);
+        }
+        let db = self.db;
+
+        match series {
+            Series::Depth(pool) => {
+                let pages = self.midgard.fetch_depth_data(&resumed, pool);
+                self.store_pages(&key, &checkpoint, pages, |interval| db.store_depth_intervals(interval)).await
+            }
+            Series::Swaps(pool) => {
+                let pages = self.midgard.fetch_swaps_data(&resumed, pool.as_ref());
+                self.store_pages(&key, &checkpoint, pages, |interval| db.store_swaps_intervals(interval)).await
+            }
+            Series::Earnings => {
+                let pages = self.midgard.fetch_earnings_data(&resumed);
+                self.store_pages(&key, &checkpoint, pages, |interval| db.store_earnings_intervals(interval)).await
+            }
+            Series::RunePool => {
+                let pages = self.midgard.fetch_runepool_data(&resumed);
+                self.store_pages(&key, &checkpoint, pages, |interval| db.store_runepool_intervals(interval)).await
+            }
+            Series::Tvl => {
+                let pages = self.midgard.fetch_tvl_data(&resumed);
+                self.store_pages(&key, &checkpoint, pages, |interval| db.store_tvl_intervals(interval)).await
+            }
+        }
+    }
+
+    // Stores every action since the actions checkpoint (or `from`), one page at a time.
+    // Midgard pages newest first, so the checkpoint only moves once a sweep reaches it; until
+    // then the token of the next page is checkpointed after every page, and an interrupted
+    // sweep resumes from it. Nothing new is fetched once shutdown is requested.
+    pub async fn ingest_actions(&self, mut params: ActionsParams, from: i64) -> Result<usize, IngestError> {
+        let from_timestamp = read_checkpoint_end(self.db, ACTIONS_CHECKPOINT).await.map_or(from, |end_time| end_time.max(from));
+        params.from_timestamp = Some(from_timestamp);
+
+        let (mut page_token, mut sweep_end) = match self.db.read_checkpoint(ACTIONS_SWEEP_CHECKPOINT).await {
+            Ok((Some(Checkpoint { end_time, page_token: Some(token), .. }), _)) => {
+                println!("Resuming actions sweep up to {}", end_time);
+                (Some(token), Some(end_time))
+            }
+            Ok(_) => (None, None),
+            Err(e) => {
+                eprintln!("Failed to read checkpoint for {}: {}", ACTIONS_SWEEP_CHECKPOINT, e);
+                (None, None)
+            }
+        };
+
+        let mut stored = 0;
+        loop {
+            let Some((actions, next_page_token)) = self.fetch(self.midgard.fetch_actions_page(&params, page_token.as_deref())).await? else {
+                println!("Stopping actions for shutdown");
+                break;
+            };
+            let _writing = self.shutdown.begin_write();
+            let page_len = actions.len();
+            for action in actions {
+                let date_secs = action.date / 1_000_000_000;
+                let duration = self.db.store_action(action).await;
+                println!("Inserted action in {:?}", duration);
+                if let Err(e) = duration {
+                    return Err(IngestError::Store { stored, error: e.to_string() });
+                }
+                stored += 1;
+                sweep_end = sweep_end.max(Some(date_secs));
+            }
+
+            // Nothing newer than the checkpoint
+            let Some(end_time) = sweep_end else { break };
+            page_token = next_page_token.filter(|_| page_len > 0);
+            if page_token.is_none() {
+                save_checkpoint(self.db, ACTIONS_CHECKPOINT, Some(end_time)).await;
+            }
+            let sweep = Checkpoint { series: ACTIONS_SWEEP_CHECKPOINT.to_string(), end_time, page_token: page_token.clone() };
+            if let Err(e) = self.db.store_checkpoint(sweep).await {
+                eprintln!("Failed to store checkpoint for {}: {}", ACTIONS_SWEEP_CHECKPOINT, e);
+            }
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(stored)
+    }
+
+    // Ingests every series, up to `concurrency` at a time. Series are independent, each
+    // with its own checkpoint, so one failing leaves the others running.
+    pub async fn ingest_all(&self, series: &[Series], range: &BackfillRange) -> IngestReport {
+        let mut outcomes = futures::stream::iter(series)
+            .map(|series| async move { (series.key(), self.ingest(series, range).await) })
+            .buffer_unordered(self.concurrency);
+
+        let mut report = IngestReport::default();
+        while let Some((key, outcome)) = outcomes.next().await {
+            match outcome {
+                Ok(stored) => {
+                    println!("{} INTERVALS INSERTED FOR {}", stored, key);
+                    report.succeeded.insert(key, stored);
+                }
+                Err(e) => {
+                    eprintln!("Failed to ingest {}: {}", key, e);
+                    report.failed.insert(key, e.to_string());
+                }
+            }
+        }
+        report
+    }
+
+    // Stores pages as they are fetched and checkpoints each one once it is fully written.
+    // Shutdown is only checked between pages: waiting for the next page is abandoned, a page
+    // being written is finished. Stops at the first failed fetch or write, so the checkpoint
+    // never gets ahead of what is stored.
+    async fn store_pages<T, M, F, Fut>(
+        &self,
+        series: &str,
+        checkpoint: &str,
+        pages: impl Stream<Item = Result<HistoryResponse<T, M>, ApiError>>,
+        store: F,
+    ) -> Result<usize, IngestError>
+    where
+        T: HistoryInterval,
+        M: Serialize + HistoryInterval,
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<Duration, Box<dyn Error>>>,
+    {
+        let mut pages = std::pin::pin!(pages);
+        let mut stored = 0;
+        loop {
+            let page = match self.fetch(pages.try_next()).await? {
+                Some(Some(page)) => page,
+                Some(None) => break,
+                None => {
+                    println!("Stopping {} for shutdown", series);
+                    break;
+                }
+            };
+            let _writing = self.shutdown.begin_write();
+            self.store_dead_letters(series, page.rejected).await;
+            let mut last_end_time = None;
+            let mut failed = None;
+            for interval in page.intervals {
+                let end_time = interval.end_time();
+                let duration = store(interval).await;
+                println!("Inserted {} interval in {:?}", series, duration);
+                if let Err(e) = duration { failed = Some(e.to_string()); break; }
+                stored += 1;
+                last_end_time = Some(end_time);
+            }
+            save_checkpoint(self.db, checkpoint, last_end_time).await;
+            if let Some(error) = failed { return Err(IngestError::Store { stored, error }); }
+            self.save_history_meta(series, &page.meta).await;
+        }
+        Ok(stored)
+    }
+
+    // Keeps the `meta` block of a fetched page alongside its intervals, for reconciliation
+    async fn save_history_meta<M: Serialize + HistoryInterval>(&self, series: &str, meta: &M) {
+        let record = match HistoryMetaRecord::new(series, meta, chrono::Utc::now().timestamp()) {
+            Ok(record) => record,
+            Err(e) => { eprintln!("Failed to serialize {} meta: {}", series, e); return; },
+        };
+        if let Err(e) = self.db.store_history_meta(record).await {
+            eprintln!("Failed to store {} meta: {}", series, e);
+        }
+    }
+
+    async fn store_dead_letters(&self, series: &str, dead_letters: Vec<DeadLetter>) {
+        for dead_letter in dead_letters {
+            eprintln!("Rejected {} record from {}: {}", series, dead_letter.source, dead_letter.error);
+            *self.rejected.lock().unwrap().entry(series.to_string()).or_insert(0) += 1;
+            if let Err(e) = self.db.store_dead_letter(dead_letter).await {
+                eprintln!("Failed to store dead letter for {}: {}", series, e);
+            }
+        }
+    }
+
+    pub fn print_rejected_summary(&self) {
+        let rejected = self.rejected.lock().unwrap();
+        if rejected.is_empty() {
+            println!("NO RECORDS REJECTED");
+            return;
+        }
+        println!("REJECTED {} RECORDS:", rejected.values().sum::<usize>());
+        for (series, count) in rejected.iter() {
+            println!("  {}: {}", series, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{save_checkpoint, BackfillRange, Ingester, Series};
+    use crate::{
+        api::api_fetcher::{ApiConfig, MidgardClient},
+        models::{Interval, TimeWindow},
+        repositories::memory_db::MemoryDb,
+        services::shutdown::Shutdown,
+    };
+
+    const HOUR: i64 = 3600;
+
+    fn range(interval: Interval) -> BackfillRange {
+        BackfillRange { window: TimeWindow::new(0, 10 * HOUR).unwrap(), interval }
+    }
+
+    // Start of `range` once resumed from whatever `db` holds for the runepool series
+    async fn resumed_start(db: &MemoryDb, interval: Interval) -> i64 {
+        let midgard = MidgardClient::new(ApiConfig::default()).unwrap();
+        let shutdown = Shutdown::from_env();
+        let ingester = Ingester::new(&midgard, db, &shutdown, 1);
+        ingester.resume_range("runepool", &range(interval)).await.window.start_time()
+    }
+
+    #[tokio::test]
+    async fn starts_at_the_window_start_without_a_checkpoint() {
+        assert_eq!(resumed_start(&MemoryDb::default(), Interval::Hour).await, 0);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_checkpoint_of_the_granularity() {
+        let db = MemoryDb::default();
+        save_checkpoint(&db, "runepool@hour", Some(3 * HOUR)).await;
+        save_checkpoint(&db, "runepool@day", Some(7 * HOUR)).await;
+
+        assert_eq!(resumed_start(&db, Interval::Hour).await, 3 * HOUR);
+        assert_eq!(resumed_start(&db, Interval::Day).await, 7 * HOUR);
+        assert_eq!(resumed_start(&db, Interval::Week).await, 0);
+    }
+
+    #[tokio::test]
+    async fn hourly_series_fall_back_to_the_unsuffixed_checkpoint() {
+        let db = MemoryDb::default();
+        save_checkpoint(&db, "runepool", Some(4 * HOUR)).await;
+
+        assert_eq!(resumed_start(&db, Interval::Hour).await, 4 * HOUR);
+        // Unsuffixed checkpoints were all hourly, so other granularities start over
+        assert_eq!(resumed_start(&db, Interval::Day).await, 0);
+
+        // Once written, the suffixed checkpoint wins
+        save_checkpoint(&db, "runepool@hour", Some(6 * HOUR)).await;
+        assert_eq!(resumed_start(&db, Interval::Hour).await, 6 * HOUR);
+    }
+
+    #[tokio::test]
+    async fn checkpoints_outside_the_window_are_clamped() {
+        let db = MemoryDb::default();
+        save_checkpoint(&db, "runepool@hour", Some(-HOUR)).await;
+        assert_eq!(resumed_start(&db, Interval::Hour).await, 0);
+
+        save_checkpoint(&db, "runepool@hour", Some(20 * HOUR)).await;
+        assert_eq!(resumed_start(&db, Interval::Hour).await, 10 * HOUR);
+    }
+
+    #[tokio::test]
+    async fn caught_up_series_fetch_nothing() {
+        let db = MemoryDb::default();
+        save_checkpoint(&db, "runepool", Some(10 * HOUR)).await;
+        // Unreachable, so any fetch would fail the ingest
+        let midgard = MidgardClient::new(ApiConfig { base_urls: vec!["http://127.0.0.1:9".to_string()], ..ApiConfig::default() }).unwrap();
+        let shutdown = Shutdown::from_env();
+        let ingester = Ingester::new(&midgard, &db, &shutdown, 1);
+
+        assert_eq!(ingester.ingest(&Series::RunePool, &range(Interval::Hour)).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{env, time::Duration};
+
+use crate::{api::api_fetcher::MidgardClient, models::Asset, services::{db_traits::Database, shutdown::Shutdown}};
+
+// Addresses whose LP positions are snapshotted, plus pools whose every member is
+#[derive(Debug, Clone, Default)]
+pub struct MemberWatchlist {
+    pub addresses: Vec<String>,
+    pub pools: Vec<Asset>,
+}
+
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .map(|raw| raw.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+impl MemberWatchlist {
+    // MEMBER_WATCHLIST and MEMBER_WATCHLIST_POOLS, both comma separated
+    pub fn from_env() -> Self {
+        Self {
+            addresses: env_list("MEMBER_WATCHLIST"),
+            pools: env_list("MEMBER_WATCHLIST_POOLS")
+                .iter()
+                .filter_map(|pool| match pool.parse() {
+                    Ok(asset) => Some(asset),
+                    Err(e) => { eprintln!("Skipping watchlist pool: {}", e); None },
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.pools.is_empty()
+    }
+}
+
+pub struct MemberSnapshotter<'a> {
+    midgard: &'a MidgardClient,
+    db: &'a dyn Database,
+    watchlist: MemberWatchlist,
+}
+
+impl<'a> MemberSnapshotter<'a> {
+    pub fn new(midgard: &'a MidgardClient, db: &'a dyn Database, watchlist: MemberWatchlist) -> Self {
+        Self { midgard, db, watchlist }
+    }
+
+    async fn addresses(&self) -> Vec<String> {
+        let mut addresses = self.watchlist.addresses.clone();
+        for pool in &self.watchlist.pools {
+            match self.midgard.fetch_members(Some(pool)).await {
+                Ok(members) => addresses.extend(members),
+                Err(e) => eprintln!("Failed to fetch members of {}: {}", pool, e),
+            }
+        }
+        addresses.sort();
+        addresses.dedup();
+        addresses
+    }
+
+    // Stores the current LP, RUNEPool and savers positions of every watched address under
+    // one snapshot time and returns how many positions were stored
+    pub async fn snapshot(&self) -> usize {
+        let snapshot_time = chrono::Utc::now().timestamp();
+        let mut stored = 0;
+
+        for address in self.addresses().await {
+            match self.midgard.fetch_member(&address).await {
+                Ok(positions) => {
+                    for mut position in positions {
+                        position.snapshot_time = snapshot_time;
+                        match self.db.store_member_position(position).await {
+                            Ok(_) => stored += 1,
+                            Err(e) => eprintln!("Failed to store position of {}: {}", address, e),
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to fetch member {}: {}", address, e),
+            }
+
+            match self.midgard.fetch_runepool_provider(&address).await {
+                Ok(providers) => {
+                    for mut provider in providers {
+                        provider.snapshot_time = snapshot_time;
+                        match self.db.store_runepool_provider(provider).await {
+                            Ok(_) => stored += 1,
+                            Err(e) => eprintln!("Failed to store RUNEPool position of {}: {}", address, e),
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to fetch RUNEPool provider {}: {}", address, e),
+            }
+
+            match self.midgard.fetch_saver(&address).await {
+                Ok(positions) => {
+                    for mut position in positions {
+                        position.snapshot_time = snapshot_time;
+                        match self.db.store_saver_position(position).await {
+                            Ok(_) => stored += 1,
+                            Err(e) => eprintln!("Failed to store savers position of {}: {}", address, e),
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to fetch saver {}: {}", address, e),
+            }
+        }
+
+        println!("Stored {} member positions", stored);
+        stored
+    }
+
+    // Takes a snapshot every `every`, starting immediately, until shutdown is requested
+    pub async fn run(&self, every: Duration, shutdown: &Shutdown) {
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {},
+                _ = shutdown.requested() => return,
+            }
+            let _writing = shutdown.begin_write();
+            self.snapshot().await;
+        }
+    }
+}
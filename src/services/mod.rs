@@ -1,2 +1,7 @@
+pub mod daemon;
 pub mod db_factory;
-pub mod db_traits;
\ No newline at end of file
+pub mod db_traits;
+pub mod ingester;
+pub mod member_snapshotter;
+pub mod replayer;
+pub mod shutdown;
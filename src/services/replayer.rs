@@ -0,0 +1,260 @@
+use std::error::Error;
+
+use crate::{
+    api::{
+        api_fetcher::{parse_actions_page, parse_history_page, parse_member, parse_runepool_provider, parse_saver},
+        response_archive::{ArchivedResponse, ResponseArchive},
+        schema_drift::SchemaDrift,
+    },
+    models::{
+        Asset, DeadLetter, DepthInterval, Interval, DepthMeta, EarningInterval, EarningsMeta, HistoryMetaRecord, HistoryResponse, PoolDetail,
+        RunePoolInterval, RunePoolMeta, SwapsInterval, SwapsMeta, TvlInterval, TvlMeta, PoolScope,
+    },
+    services::{db_traits::Database, shutdown::Shutdown},
+};
+
+#[derive(Debug, Default)]
+pub struct ReplaySummary {
+    pub replayed: usize,
+    // Responses of endpoints that hold nothing to store, e.g. `/members`
+    pub skipped: usize,
+    pub failed: usize,
+    // Intervals that still do not parse, stored as dead letters again
+    pub rejected: usize,
+}
+
+// Loads archived Midgard responses into the database without touching the network
+pub struct Replayer<'a> {
+    db: &'a dyn Database,
+    archive: ResponseArchive,
+    drift: &'a SchemaDrift,
+    shutdown: &'a Shutdown,
+}
+
+impl<'a> Replayer<'a> {
+    pub fn new(db: &'a dyn Database, archive: ResponseArchive, drift: &'a SchemaDrift, shutdown: &'a Shutdown) -> Self {
+        Self { db, archive, drift, shutdown }
+    }
+
+    // Re-parses every archived response, oldest first so newer fetches win the upserts.
+    // Checkpoints are left alone; the next regular run still resumes where it stopped.
+    // Stops between responses once shutdown is requested.
+    pub async fn replay(&self) -> std::io::Result<ReplaySummary> {
+        let mut summary = ReplaySummary::default();
+
+        for file in self.archive.list().await? {
+            if self.shutdown.is_requested() {
+                println!("Stopping replay for shutdown");
+                break;
+            }
+            let _writing = self.shutdown.begin_write();
+            let response = match self.archive.read(&file).await {
+                Ok(response) => response,
+                Err(e) => {
+                    eprintln!("Failed to read archived response {}: {}", file.display(), e);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            match self.load(response, &mut summary.rejected).await {
+                Ok(true) => summary.replayed += 1,
+                Ok(false) => summary.skipped += 1,
+                Err(e) => {
+                    eprintln!("Failed to replay {}: {}", file.display(), e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        println!(
+            "Replayed {} responses ({} skipped, {} failed, {} records rejected)",
+            summary.replayed, summary.skipped, summary.failed, summary.rejected
+        );
+        Ok(summary)
+    }
+
+    async fn store_rejected(&self, dead_letters: Vec<DeadLetter>, rejected: &mut usize) -> Result<(), Box<dyn Error>> {
+        for dead_letter in dead_letters {
+            self.db.store_dead_letter(dead_letter).await?;
+            *rejected += 1;
+        }
+        Ok(())
+    }
+
+    // Stores the records of one response the same way a live fetch would;
+    // `false` when the endpoint holds nothing to store
+    async fn load(&self, response: ArchivedResponse, rejected: &mut usize) -> Result<bool, Box<dyn Error>> {
+        // Intervals ending after this were still open when fetched; the live backfill never stores those
+        let fetched_at = response.fetched_at / 1000;
+        let endpoint = response.endpoint().to_string();
+        let pool: Option<Asset> = response.param("pool").map(str::parse).transpose()?;
+        let granularity: Interval = response.param("interval").map(str::parse).transpose()?.unwrap_or_default();
+        let source = response.path;
+        let body = response.body;
+
+        let segments: Vec<&str> = endpoint.trim_start_matches('/').splitn(3, '/').collect();
+        match segments.as_slice() {
+            ["history", "depths", asset] => {
+                let asset: Asset = asset.parse()?;
+                let mut page: HistoryResponse<DepthInterval, DepthMeta> = parse_history_page(body, &source, self.drift)?;
+                self.store_rejected(page.rejected, rejected).await?;
+                page.intervals.retain(|interval| interval.end_time <= fetched_at);
+                for mut interval in page.intervals {
+                    interval.pool = Some(asset.clone());
+                    interval.granularity = granularity;
+                    self.db.store_depth_intervals(interval).await?;
+                }
+                let series = format!("depth:{}", asset);
+                self.db.store_history_meta(HistoryMetaRecord::new(&series, &page.meta, fetched_at)?).await?;
+            }
+            ["history", "swaps"] => {
+                let mut page: HistoryResponse<SwapsInterval, SwapsMeta> = parse_history_page(body, &source, self.drift)?;
+                self.store_rejected(page.rejected, rejected).await?;
+                page.intervals.retain(|interval| interval.end_time <= fetched_at);
+                let pool_tag = PoolScope::from(pool.clone());
+                for mut interval in page.intervals {
+                    interval.pool = pool_tag.clone();
+                    interval.granularity = granularity;
+                    self.db.store_swaps_intervals(interval).await?;
+                }
+                page.meta.pool = pool_tag;
+                page.meta.granularity = granularity;
+                let series = match &pool {
+                    Some(pool) => format!("swaps:{}", pool),
+                    None => "swaps".to_string(),
+                };
+                self.db.store_history_meta(HistoryMetaRecord::new(&series, &page.meta, fetched_at)?).await?;
+            }
+            ["history", "earnings"] => {
+                let mut page: HistoryResponse<EarningInterval, EarningsMeta> = parse_history_page(body, &source, self.drift)?;
+                self.store_rejected(page.rejected, rejected).await?;
+                page.intervals.retain(|interval| interval.end_time <= fetched_at);
+                for mut interval in page.intervals {
+                    interval.granularity = granularity;
+                    self.db.store_earnings_intervals(interval).await?;
+                }
+                page.meta.granularity = granularity;
+                self.db.store_history_meta(HistoryMetaRecord::new("earnings", &page.meta, fetched_at)?).await?;
+            }
+            ["history", "runepool"] => {
+                let mut page: HistoryResponse<RunePoolInterval, RunePoolMeta> = parse_history_page(body, &source, self.drift)?;
+                self.store_rejected(page.rejected, rejected).await?;
+                page.intervals.retain(|interval| interval.end_time <= fetched_at);
+                for mut interval in page.intervals {
+                    interval.granularity = granularity;
+                    self.db.store_runepool_intervals(interval).await?;
+                }
+                self.db.store_history_meta(HistoryMetaRecord::new("runepool", &page.meta, fetched_at)?).await?;
+            }
+            ["history", "tvl"] => {
+                let mut page: HistoryResponse<TvlInterval, TvlMeta> = parse_history_page(body, &source, self.drift)?;
+                self.store_rejected(page.rejected, rejected).await?;
+                page.intervals.retain(|interval| interval.end_time <= fetched_at);
+                for mut interval in page.intervals {
+                    interval.granularity = granularity;
+                    self.db.store_tvl_intervals(interval).await?;
+                }
+                page.meta.granularity = granularity;
+                self.db.store_history_meta(HistoryMetaRecord::new("tvl", &page.meta, fetched_at)?).await?;
+            }
+            ["pools"] => {
+                let pools: Vec<PoolDetail> = serde_json::from_value(body)?;
+                for mut pool in pools {
+                    pool.snapshot_time = fetched_at;
+                    self.db.store_pool_snapshot(pool).await?;
+                }
+            }
+            ["pool", _] => {
+                let mut pool: PoolDetail = serde_json::from_value(body)?;
+                pool.snapshot_time = fetched_at;
+                self.db.store_pool_snapshot(pool).await?;
+            }
+            ["actions"] => {
+                let (actions, _) = parse_actions_page(body)?;
+                for action in actions {
+                    self.db.store_action(action).await?;
+                }
+            }
+            ["member", address] => {
+                for mut position in parse_member(body, address)? {
+                    position.snapshot_time = fetched_at;
+                    self.db.store_member_position(position).await?;
+                }
+            }
+            ["runepool", address] => {
+                for mut provider in parse_runepool_provider(body, address)? {
+                    provider.snapshot_time = fetched_at;
+                    self.db.store_runepool_provider(provider).await?;
+                }
+            }
+            ["saver", address] => {
+                for mut position in parse_saver(body, address)? {
+                    position.snapshot_time = fetched_at;
+                    self.db.store_saver_position(position).await?;
+                }
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde_json::{json, Value};
+
+    use super::Replayer;
+    use crate::{
+        api::{response_archive::ResponseArchive, schema_drift::SchemaDrift},
+        models::Interval,
+        repositories::memory_db::MemoryDb,
+        services::{db_traits::Database, shutdown::Shutdown},
+    };
+
+    const PATH: &str = "/history/runepool?interval=hour&count=400&from=0";
+
+    fn runepool_page(intervals: &[(i64, &str)]) -> Value {
+        let intervals: Vec<_> = intervals
+            .iter()
+            .map(|(start, units)| json!({ "count": "1", "startTime": start.to_string(), "endTime": (start + 3600).to_string(), "units": units }))
+            .collect();
+        json!({
+            "meta": { "startTime": "0", "endTime": "7200", "startCount": "1", "endCount": "1", "startUnits": "10", "endUnits": "20" },
+            "intervals": intervals,
+        })
+    }
+
+    #[tokio::test]
+    async fn replays_archived_responses_oldest_first() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("replayer-test-{}-{}", std::process::id(), nanos));
+        let archive = ResponseArchive::new(&dir);
+
+        archive.write(PATH, &runepool_page(&[(0, "10"), (3600, "20")])).await.unwrap();
+        // Archive names are per millisecond
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        // Refetched: the second interval changed and one is still open, so it is not stored
+        archive.write(PATH, &runepool_page(&[(3600, "25"), (i64::from(i32::MAX), "30")])).await.unwrap();
+        archive.write("/members", &json!({ "members": [] })).await.unwrap();
+
+        let db = MemoryDb::default();
+        let drift = SchemaDrift::new(false);
+        let shutdown = Shutdown::from_env();
+        let summary = Replayer::new(&db, archive, &drift, &shutdown).replay().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        let summary = summary.unwrap();
+
+        assert_eq!((summary.replayed, summary.skipped, summary.failed, summary.rejected), (2, 1, 0, 0));
+        let (mut intervals, _) = db.read_runepool_intervals().await.unwrap();
+        intervals.sort_by_key(|interval| interval.end_time);
+        let stored: Vec<_> = intervals
+            .iter()
+            .map(|interval| (interval.end_time, interval.units.to_string(), interval.granularity))
+            .collect();
+        assert_eq!(stored, [(3600, "10".to_string(), Interval::Hour), (7200, "25".to_string(), Interval::Hour)]);
+        assert_eq!(db.read_history_metas().await.unwrap().0.len(), 1);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    env,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::sync::watch;
+
+// Coordinates a clean stop on SIGINT/SIGTERM: once requested no new fetches are started,
+// while batches already being written are tracked so they can finish before exit
+#[derive(Debug)]
+pub struct Shutdown {
+    requested: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    // How long in-flight writes get to finish once shutdown is requested
+    pub timeout: Duration,
+}
+
+// Marks a batch of writes as in flight until dropped
+pub struct InFlight<'a>(&'a Shutdown);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Shutdown {
+    // SHUTDOWN_TIMEOUT_SECS, default 30s
+    pub fn from_env() -> Self {
+        let timeout = env::var("SHUTDOWN_TIMEOUT_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        Self {
+            requested: watch::channel(false).0,
+            in_flight: AtomicUsize::new(0),
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.requested.send_replace(true);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    // Resolves once shutdown is requested, immediately if it already was
+    pub async fn requested(&self) {
+        let mut requested = self.requested.subscribe();
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+
+    pub fn begin_write(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(self)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    // Waits for the first SIGINT or SIGTERM and requests shutdown
+    pub async fn listen_for_signals(&self) {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                },
+                Err(e) => {
+                    eprintln!("Failed to listen for SIGTERM: {}", e);
+                    let _ = tokio::signal::ctrl_c().await;
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+
+        println!("Shutdown requested, waiting up to {:?} for in-flight writes", self.timeout);
+        self.trigger();
+    }
+}